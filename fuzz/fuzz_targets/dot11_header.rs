@@ -0,0 +1,7 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use nearby::dot11::header::Dot11Header;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Dot11Header::from_bytes(data);
+});
@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use nearby::dot11::header::{FrameAddresses, FrameControl};
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 2 {
+        return;
+    }
+    if let Ok(frame_control) = FrameControl::from_bytes(&data[..2]) {
+        let _ = FrameAddresses::from_bytes(&data[2..], frame_control);
+    }
+});
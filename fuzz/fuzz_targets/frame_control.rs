@@ -0,0 +1,7 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use nearby::dot11::header::FrameControl;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = FrameControl::from_bytes(data);
+});
@@ -0,0 +1,37 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use nearby::dot11::elements::decode_elements;
+use nearby::dot11::info::*;
+use nearby::dot11::phy::*;
+use nearby::dot11::security::*;
+
+// The first byte selects the parser, the rest is its input
+fuzz_target!(|data: &[u8]| {
+    if data.is_empty() {
+        return;
+    }
+
+    let input = &data[1..];
+    match data[0] % 20 {
+        0 => drop(Beacon::from_bytes(input)),
+        1 => drop(ProbeRequest::from_bytes(input)),
+        2 => drop(ProbeResponse::from_bytes(input)),
+        3 => drop(AssociationRequest::from_bytes(input)),
+        4 => drop(AssociationResponse::from_bytes(input)),
+        5 => drop(ReassociationRequest::from_bytes(input)),
+        6 => drop(SSID::from_bytes(input)),
+        7 => drop(Country::from_bytes(input)),
+        8 => drop(TaggedParameters::from_bytes(input)),
+        9 => drop(RsnInfo::from_bytes(input)),
+        10 => drop(HtCapabilities::from_bytes(input)),
+        11 => drop(HtOperation::from_bytes(input)),
+        12 => drop(VhtCapabilities::from_bytes(input)),
+        13 => drop(VhtOperation::from_bytes(input)),
+        14 => drop(HeCapabilities::from_bytes(input)),
+        15 => drop(HeOperation::from_bytes(input)),
+        16 => drop(EhtOperation::from_bytes(input)),
+        17 => drop(EhtCapabilities::from_bytes(input)),
+        18 => drop(RsnInfo::from_wpa_vendor_bytes(input)),
+        _ => drop(decode_elements(input)),
+    }
+});
@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use nearby::scanner::Scanner;
+
+// Whole captured frames: radiotap header followed by the 802.11 frame
+fuzz_target!(|data: &[u8]| {
+    let _ = Scanner::parse(data);
+});
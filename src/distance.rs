@@ -0,0 +1,163 @@
+use crate::errors::*;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// Free-space path loss constant for distances in meters and frequencies in MHz
+const FREE_SPACE_PATH_LOSS: f32 = 27.55;
+// Samples needed for a vendor to get its own offset
+const MIN_VENDOR_SAMPLES: usize = 2;
+
+// Estimates how far a transmitter is from its received signal
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(tag = "model")]
+pub enum DistanceModel {
+    // https://en.wikipedia.org/wiki/Free-space_path_loss, assumes a 0 dBm transmitter
+    #[default]
+    FreeSpace,
+    LogDistance(LogDistance),
+}
+
+impl DistanceModel {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<DistanceModel> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    // Meters, freq in MHz and signal in dBm
+    pub fn distance(&self, signal: i8, freq: f32, vendor: &str) -> f32 {
+        match self {
+            DistanceModel::FreeSpace => {
+                let exponent =
+                    (FREE_SPACE_PATH_LOSS - (20.0 * freq.log10()) - f32::from(signal)) / 20.0;
+                10_f32.powf(exponent)
+            }
+            DistanceModel::LogDistance(model) => model.distance(signal, vendor),
+        }
+    }
+}
+
+// https://en.wikipedia.org/wiki/Log-distance_path_loss_model
+// RSSI(d) = RSSI(1 m) + offset - 10 n log10(d)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct LogDistance {
+    pub exponent: f32,                        // n: 2 in free space, 2.7 to 4 indoors
+    pub reference_rssi: f32,                  // dBm at 1 meter
+    pub vendor_offsets: HashMap<String, f32>, // dB, devices transmitting louder than usual
+}
+
+impl Default for LogDistance {
+    fn default() -> LogDistance {
+        LogDistance {
+            exponent: 3.0,
+            reference_rssi: -40.0,
+            vendor_offsets: HashMap::new(),
+        }
+    }
+}
+
+impl LogDistance {
+    pub fn distance(&self, signal: i8, vendor: &str) -> f32 {
+        let offset = self.vendor_offsets.get(vendor).copied().unwrap_or_default();
+        let exponent = (self.reference_rssi + offset - f32::from(signal)) / (10.0 * self.exponent);
+        10_f32.powf(exponent)
+    }
+
+    // Least squares fit of the RSSI against log10 of the distance. Vendors with
+    // enough samples get their own intercept, so the exponent is shared and isn't
+    // skewed by louder devices; their offset is the difference to the intercept of
+    // the other samples.
+    pub fn fit(samples: &[CalibrationSample]) -> Result<LogDistance> {
+        if samples.iter().any(|sample| sample.distance <= 0.0) {
+            bail!(ErrorKind::Calibration("distances must be positive".into()));
+        }
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for vendor in samples.iter().filter_map(|sample| sample.vendor.as_deref()) {
+            *counts.entry(vendor).or_default() += 1;
+        }
+        // Vendors with too few samples are pooled with the samples without a vendor
+        let mut groups: HashMap<Option<&str>, Vec<(f32, f32)>> = HashMap::new();
+        for sample in samples {
+            let vendor = sample
+                .vendor
+                .as_deref()
+                .filter(|vendor| counts[vendor] >= MIN_VENDOR_SAMPLES);
+            groups
+                .entry(vendor)
+                .or_default()
+                .push((sample.distance.log10(), f32::from(sample.signal)));
+        }
+
+        let mut variance = 0.0;
+        let mut covariance = 0.0;
+        for points in groups.values() {
+            let (mean_x, mean_y) = centroid(points);
+            for (x, y) in points {
+                variance += (x - mean_x).powi(2);
+                covariance += (x - mean_x) * (y - mean_y);
+            }
+        }
+        if variance <= f32::EPSILON {
+            bail!(ErrorKind::Calibration(
+                "samples at two different distances at least are needed".into()
+            ));
+        }
+
+        let slope = covariance / variance;
+        if slope >= 0.0 {
+            bail!(ErrorKind::Calibration(
+                "the signal doesn't decrease with the distance".into()
+            ));
+        }
+        let intercept = |points: &[(f32, f32)]| {
+            let (mean_x, mean_y) = centroid(points);
+            mean_y - slope * mean_x
+        };
+        // Without samples of unknown vendors, the reference is the average device
+        let reference_rssi = match groups.get(&None) {
+            Some(points) => intercept(points),
+            None => intercept(&groups.values().flatten().copied().collect::<Vec<_>>()),
+        };
+        let vendor_offsets = groups
+            .iter()
+            .filter_map(|(vendor, points)| {
+                Some((
+                    vendor.as_ref()?.to_string(),
+                    intercept(points) - reference_rssi,
+                ))
+            })
+            .collect();
+        Ok(LogDistance {
+            exponent: -slope / 10.0,
+            reference_rssi,
+            vendor_offsets,
+        })
+    }
+}
+
+// Mean of the x and y coordinates
+fn centroid(points: &[(f32, f32)]) -> (f32, f32) {
+    let count = points.len() as f32;
+    let x = points.iter().map(|(x, _)| x).sum::<f32>() / count;
+    let y = points.iter().map(|(_, y)| y).sum::<f32>() / count;
+    (x, y)
+}
+
+// A signal received from a device at a measured distance
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CalibrationSample {
+    pub signal: i8,    // dBm
+    pub distance: f32, // meters
+    #[serde(default)]
+    pub vendor: Option<String>,
+}
+
+impl CalibrationSample {
+    // A JSON array of samples
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Vec<CalibrationSample>> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
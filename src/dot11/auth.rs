@@ -0,0 +1,316 @@
+use crate::errors::*;
+use crate::dot11::elements::*;
+use crate::dot11::info::Info;
+use crate::dot11::reader::Reader;
+use crate::dot11::writer::{Encode, Writer};
+use serde::{Serialize, Deserialize};
+
+// Authentication Algorithm Number (IEEE 802.11-2020 9.4.1.1)
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AuthAlgorithm {
+    OpenSystem,
+    SharedKey,
+    FastBssTransition,
+    Sae,
+    FilsSharedKey,
+    FilsSharedKeyPfs,
+    FilsPublicKey,
+    VendorSpecific,
+    Unknown(u16),
+}
+
+impl AuthAlgorithm {
+    pub fn from_number(number: u16) -> AuthAlgorithm {
+        match number {
+            0 => AuthAlgorithm::OpenSystem,
+            1 => AuthAlgorithm::SharedKey,
+            2 => AuthAlgorithm::FastBssTransition,
+            3 => AuthAlgorithm::Sae,
+            4 => AuthAlgorithm::FilsSharedKey,
+            5 => AuthAlgorithm::FilsSharedKeyPfs,
+            6 => AuthAlgorithm::FilsPublicKey,
+            0xffff => AuthAlgorithm::VendorSpecific,
+            _ => AuthAlgorithm::Unknown(number),
+        }
+    }
+
+    pub fn number(self) -> u16 {
+        match self {
+            AuthAlgorithm::OpenSystem => 0,
+            AuthAlgorithm::SharedKey => 1,
+            AuthAlgorithm::FastBssTransition => 2,
+            AuthAlgorithm::Sae => 3,
+            AuthAlgorithm::FilsSharedKey => 4,
+            AuthAlgorithm::FilsSharedKeyPfs => 5,
+            AuthAlgorithm::FilsPublicKey => 6,
+            AuthAlgorithm::VendorSpecific => 0xffff,
+            AuthAlgorithm::Unknown(number) => number,
+        }
+    }
+
+    // Transaction sequence number of the frame that ends the exchange, the challenge
+    // of Shared Key takes two more frames and SAE ends with the Confirm messages
+    pub fn final_sequence(self) -> u16 {
+        match self {
+            AuthAlgorithm::SharedKey => 4,
+            _ => 2,
+        }
+    }
+}
+
+// Status Code (IEEE 802.11-2020 Table 9-50), the ones seen during authentication
+// and association
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StatusCode {
+    Success,
+    Unspecified,
+    CapabilitiesMismatch,
+    ReassociationDenied,
+    AssociationDenied,
+    UnsupportedAuthAlgorithm,
+    AuthSequenceOutOfOrder,
+    ChallengeFailure,
+    AuthTimeout,
+    ApFull,
+    BasicRatesMismatch,
+    RefusedTemporarily,
+    RobustManagementPolicyViolation,
+    RequestDeclined,
+    InvalidElement,
+    InvalidGroupCipher,
+    InvalidPairwiseCipher,
+    InvalidAkmp,
+    InvalidPmkid,
+    AntiCloggingTokenRequired,
+    UnsupportedFiniteCyclicGroup,
+    Unknown(u16),
+}
+
+impl StatusCode {
+    pub fn from_code(code: u16) -> StatusCode {
+        match code {
+            0 => StatusCode::Success,
+            1 => StatusCode::Unspecified,
+            10 => StatusCode::CapabilitiesMismatch,
+            11 => StatusCode::ReassociationDenied,
+            12 => StatusCode::AssociationDenied,
+            13 => StatusCode::UnsupportedAuthAlgorithm,
+            14 => StatusCode::AuthSequenceOutOfOrder,
+            15 => StatusCode::ChallengeFailure,
+            16 => StatusCode::AuthTimeout,
+            17 => StatusCode::ApFull,
+            18 => StatusCode::BasicRatesMismatch,
+            30 => StatusCode::RefusedTemporarily,
+            31 => StatusCode::RobustManagementPolicyViolation,
+            37 => StatusCode::RequestDeclined,
+            40 => StatusCode::InvalidElement,
+            41 => StatusCode::InvalidGroupCipher,
+            42 => StatusCode::InvalidPairwiseCipher,
+            43 => StatusCode::InvalidAkmp,
+            53 => StatusCode::InvalidPmkid,
+            76 => StatusCode::AntiCloggingTokenRequired,
+            77 => StatusCode::UnsupportedFiniteCyclicGroup,
+            _ => StatusCode::Unknown(code),
+        }
+    }
+
+    pub fn code(self) -> u16 {
+        match self {
+            StatusCode::Success => 0,
+            StatusCode::Unspecified => 1,
+            StatusCode::CapabilitiesMismatch => 10,
+            StatusCode::ReassociationDenied => 11,
+            StatusCode::AssociationDenied => 12,
+            StatusCode::UnsupportedAuthAlgorithm => 13,
+            StatusCode::AuthSequenceOutOfOrder => 14,
+            StatusCode::ChallengeFailure => 15,
+            StatusCode::AuthTimeout => 16,
+            StatusCode::ApFull => 17,
+            StatusCode::BasicRatesMismatch => 18,
+            StatusCode::RefusedTemporarily => 30,
+            StatusCode::RobustManagementPolicyViolation => 31,
+            StatusCode::RequestDeclined => 37,
+            StatusCode::InvalidElement => 40,
+            StatusCode::InvalidGroupCipher => 41,
+            StatusCode::InvalidPairwiseCipher => 42,
+            StatusCode::InvalidAkmp => 43,
+            StatusCode::InvalidPmkid => 53,
+            StatusCode::AntiCloggingTokenRequired => 76,
+            StatusCode::UnsupportedFiniteCyclicGroup => 77,
+            StatusCode::Unknown(code) => code,
+        }
+    }
+}
+
+// Reason Code (IEEE 802.11-2020 Table 9-49) of deauthentication and disassociation
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ReasonCode {
+    Unspecified,
+    InvalidAuthentication,
+    LeavingNetworkDeauth,
+    Inactivity,
+    NoMoreStations,
+    Class2FrameFromNonAuthStation,
+    Class3FrameFromNonAssocStation,
+    LeavingNetworkDisassoc,
+    NotAuthenticated,
+    UnacceptablePowerCapability,
+    UnacceptableSupportedChannels,
+    BssTransitionDisassoc,
+    InvalidElement,
+    MicFailure,
+    FourWayHandshakeTimeout,
+    GroupKeyHandshakeTimeout,
+    HandshakeElementMismatch,
+    InvalidGroupCipher,
+    InvalidPairwiseCipher,
+    InvalidAkmp,
+    UnsupportedRsneVersion,
+    InvalidRsneCapabilities,
+    Ieee8021XAuthFailed,
+    CipherSuiteRejected,
+    Unknown(u16),
+}
+
+impl ReasonCode {
+    pub fn from_code(code: u16) -> ReasonCode {
+        match code {
+            1 => ReasonCode::Unspecified,
+            2 => ReasonCode::InvalidAuthentication,
+            3 => ReasonCode::LeavingNetworkDeauth,
+            4 => ReasonCode::Inactivity,
+            5 => ReasonCode::NoMoreStations,
+            6 => ReasonCode::Class2FrameFromNonAuthStation,
+            7 => ReasonCode::Class3FrameFromNonAssocStation,
+            8 => ReasonCode::LeavingNetworkDisassoc,
+            9 => ReasonCode::NotAuthenticated,
+            10 => ReasonCode::UnacceptablePowerCapability,
+            11 => ReasonCode::UnacceptableSupportedChannels,
+            12 => ReasonCode::BssTransitionDisassoc,
+            13 => ReasonCode::InvalidElement,
+            14 => ReasonCode::MicFailure,
+            15 => ReasonCode::FourWayHandshakeTimeout,
+            16 => ReasonCode::GroupKeyHandshakeTimeout,
+            17 => ReasonCode::HandshakeElementMismatch,
+            18 => ReasonCode::InvalidGroupCipher,
+            19 => ReasonCode::InvalidPairwiseCipher,
+            20 => ReasonCode::InvalidAkmp,
+            21 => ReasonCode::UnsupportedRsneVersion,
+            22 => ReasonCode::InvalidRsneCapabilities,
+            23 => ReasonCode::Ieee8021XAuthFailed,
+            24 => ReasonCode::CipherSuiteRejected,
+            _ => ReasonCode::Unknown(code),
+        }
+    }
+
+    pub fn code(self) -> u16 {
+        match self {
+            ReasonCode::Unspecified => 1,
+            ReasonCode::InvalidAuthentication => 2,
+            ReasonCode::LeavingNetworkDeauth => 3,
+            ReasonCode::Inactivity => 4,
+            ReasonCode::NoMoreStations => 5,
+            ReasonCode::Class2FrameFromNonAuthStation => 6,
+            ReasonCode::Class3FrameFromNonAssocStation => 7,
+            ReasonCode::LeavingNetworkDisassoc => 8,
+            ReasonCode::NotAuthenticated => 9,
+            ReasonCode::UnacceptablePowerCapability => 10,
+            ReasonCode::UnacceptableSupportedChannels => 11,
+            ReasonCode::BssTransitionDisassoc => 12,
+            ReasonCode::InvalidElement => 13,
+            ReasonCode::MicFailure => 14,
+            ReasonCode::FourWayHandshakeTimeout => 15,
+            ReasonCode::GroupKeyHandshakeTimeout => 16,
+            ReasonCode::HandshakeElementMismatch => 17,
+            ReasonCode::InvalidGroupCipher => 18,
+            ReasonCode::InvalidPairwiseCipher => 19,
+            ReasonCode::InvalidAkmp => 20,
+            ReasonCode::UnsupportedRsneVersion => 21,
+            ReasonCode::InvalidRsneCapabilities => 22,
+            ReasonCode::Ieee8021XAuthFailed => 23,
+            ReasonCode::CipherSuiteRejected => 24,
+            ReasonCode::Unknown(code) => code,
+        }
+    }
+}
+
+// Authentication frame body (IEEE 802.11-2020 9.3.3.11)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Authentication {
+    pub algorithm: AuthAlgorithm,
+    pub sequence: u16,
+    pub status: StatusCode,
+    pub sae_fields: Vec<u8>, // SAE Commit/Confirm fields, they aren't elements
+    pub elements: Vec<InformationElement>, // challenge text, FT and FILS elements
+}
+
+impl Info for Authentication {
+    fn from_bytes(input: &[u8]) -> Result<Authentication> {
+        let mut reader = Reader::new(input);
+
+        let algorithm = AuthAlgorithm::from_number(reader.u16()?);
+        let sequence = reader.u16()?;
+        let status = StatusCode::from_code(reader.u16()?);
+
+        let (sae_fields, elements) = match algorithm {
+            AuthAlgorithm::Sae => (reader.rest().to_vec(), Vec::new()),
+            _ => (Vec::new(), decode_elements(reader.rest())),
+        };
+
+        Ok(Authentication {
+            algorithm,
+            sequence,
+            status,
+            sae_fields,
+            elements,
+        })
+    }
+}
+
+impl Encode for Authentication {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u16(self.algorithm.number());
+        writer.u16(self.sequence);
+        writer.u16(self.status.code());
+        writer.bytes(&self.sae_fields);
+        for element in &self.elements {
+            element.encode(writer);
+        }
+    }
+}
+
+impl Authentication {
+    // True for the frame that ends a successful authentication
+    pub fn completes(&self) -> bool {
+        self.status == StatusCode::Success && self.sequence == self.algorithm.final_sequence()
+    }
+}
+
+// Deauthentication and Disassociation frame bodies (IEEE 802.11-2020 9.3.3.5 and 9.3.3.13)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deauthentication {
+    pub reason: ReasonCode,
+    pub elements: Vec<InformationElement>, // vendor specific elements and the MME
+}
+
+impl Info for Deauthentication {
+    fn from_bytes(input: &[u8]) -> Result<Deauthentication> {
+        let mut reader = Reader::new(input);
+
+        let reason = ReasonCode::from_code(reader.u16()?);
+
+        Ok(Deauthentication {
+            reason,
+            elements: decode_elements(reader.rest()),
+        })
+    }
+}
+
+impl Encode for Deauthentication {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u16(self.reason.code());
+        for element in &self.elements {
+            element.encode(writer);
+        }
+    }
+}
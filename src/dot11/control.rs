@@ -0,0 +1,94 @@
+use crate::errors::*;
+use crate::dot11::info::Info;
+use crate::dot11::reader::Reader;
+use crate::dot11::writer::{Encode, Writer};
+
+const BA_CONTROL_MULTI_TID: u16 = 0x0002;
+const BA_CONTROL_COMPRESSED_BITMAP: u16 = 0x0004;
+
+// Block Ack Request body (IEEE 802.11-2020 9.3.1.7), after the TA
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockAckRequest {
+    pub control: u16,
+    pub tid: u8,
+    pub starting_sequence: u16,
+    pub information: Vec<u8>, // per TID information of Multi-TID requests
+}
+
+impl Info for BlockAckRequest {
+    fn from_bytes(input: &[u8]) -> Result<BlockAckRequest> {
+        let mut reader = Reader::new(input);
+
+        let control = reader.u16()?;
+        let starting_sequence = match control & BA_CONTROL_MULTI_TID {
+            0 => reader.u16()? >> 4,
+            _ => 0,
+        };
+
+        Ok(BlockAckRequest {
+            control,
+            tid: tid_info(control),
+            starting_sequence,
+            information: reader.rest().to_vec(),
+        })
+    }
+}
+
+impl Encode for BlockAckRequest {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u16(self.control);
+        if self.control & BA_CONTROL_MULTI_TID == 0 {
+            writer.u16(self.starting_sequence << 4);
+        }
+        writer.bytes(&self.information);
+    }
+}
+
+// Block Ack body (IEEE 802.11-2020 9.3.1.8), after the TA
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockAck {
+    pub control: u16,
+    pub tid: u8,
+    pub starting_sequence: u16,
+    pub bitmap: Vec<u8>, // per TID information of Multi-TID and Multi-STA block acks
+}
+
+impl Info for BlockAck {
+    fn from_bytes(input: &[u8]) -> Result<BlockAck> {
+        let mut reader = Reader::new(input);
+
+        let control = reader.u16()?;
+        let starting_sequence = match control & BA_CONTROL_MULTI_TID {
+            0 => reader.u16()? >> 4,
+            _ => 0,
+        };
+
+        Ok(BlockAck {
+            control,
+            tid: tid_info(control),
+            starting_sequence,
+            bitmap: reader.rest().to_vec(),
+        })
+    }
+}
+
+impl Encode for BlockAck {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u16(self.control);
+        if self.control & BA_CONTROL_MULTI_TID == 0 {
+            writer.u16(self.starting_sequence << 4);
+        }
+        writer.bytes(&self.bitmap);
+    }
+}
+
+impl BlockAck {
+    pub fn is_compressed(&self) -> bool {
+        self.control & BA_CONTROL_COMPRESSED_BITMAP > 0
+    }
+}
+
+// TID_INFO, bits 12-15 of the BAR/BA Control field
+fn tid_info(control: u16) -> u8 {
+    (control >> 12) as u8
+}
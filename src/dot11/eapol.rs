@@ -0,0 +1,190 @@
+use crate::errors::*;
+use crate::dot11::info::Info;
+use crate::dot11::reader::Reader;
+use crate::dot11::writer::{Encode, Writer};
+use serde::{Serialize, Deserialize};
+
+// LLC/SNAP header carrying the 802.1X (EAPOL) ethertype 0x888e
+const LLC_SNAP_EAPOL: [u8; 8] = [0xaa, 0xaa, 0x03, 0x00, 0x00, 0x00, 0x88, 0x8e];
+const EAPOL_KEY: u8 = 3;
+// Descriptor type, Key Information, Key Length, Replay Counter, Nonce, IV, RSC and Reserved
+const KEY_FIXED_LEN: usize = 1 + 2 + 2 + 8 + 32 + 16 + 8 + 8;
+const MIC_LEN: usize = 16;
+// Suite B 192 and FT-SHA384 AKMs use a 24 bytes MIC
+const MIC_LEN_SHA384: usize = 24;
+
+// Key Information bits (IEEE 802.11-2020 Figure 12-33)
+const KEY_INFO_PAIRWISE: u16 = 0x0008;
+const KEY_INFO_ACK: u16 = 0x0080;
+const KEY_INFO_MIC: u16 = 0x0100;
+const KEY_INFO_SECURE: u16 = 0x0200;
+const KEY_INFO_ERROR: u16 = 0x0400;
+const KEY_INFO_REQUEST: u16 = 0x0800;
+const KEY_INFO_ENCRYPTED_KEY_DATA: u16 = 0x1000;
+
+// PMKID KDE, 00-0F-AC:4
+const KDE_TYPE: u8 = 0xdd;
+const KDE_PMKID: [u8; 4] = [0x00, 0x0f, 0xac, 0x04];
+
+// Messages of the 4-way handshake
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum HandshakeMessage {
+    M1,
+    M2,
+    M3,
+    M4,
+}
+
+// EAPOL-Key frame (IEEE 802.11-2020 12.7.2) found in the body of data frames,
+// the integers are big endian unlike the rest of the 802.11 frame
+#[derive(Clone, Debug, PartialEq)]
+pub struct EapolKey {
+    pub version: u8,         // 802.1X protocol version
+    pub descriptor_type: u8, // 2 = RSN, 254 = WPA
+    pub key_information: u16,
+    pub key_length: u16,
+    pub replay_counter: u64,
+    pub nonce: [u8; 32],
+    pub iv: [u8; 16],
+    pub rsc: [u8; 8],
+    pub reserved: [u8; 8],
+    pub mic: Vec<u8>,
+    pub key_data: Vec<u8>,
+}
+
+impl EapolKey {
+    // True when the data frame body is LLC/SNAP followed by an EAPOL-Key packet
+    pub fn is_eapol_key(input: &[u8]) -> bool {
+        input.len() > LLC_SNAP_EAPOL.len() + 1
+            && input[..LLC_SNAP_EAPOL.len()] == LLC_SNAP_EAPOL
+            && input[LLC_SNAP_EAPOL.len() + 1] == EAPOL_KEY
+    }
+
+    pub fn key_version(&self) -> u8 {
+        (self.key_information & 0x0007) as u8
+    }
+
+    // Position in the 4-way handshake, None for group key handshakes and requests
+    pub fn message(&self) -> Option<HandshakeMessage> {
+        let info = self.key_information;
+        if info & KEY_INFO_PAIRWISE == 0 || info & (KEY_INFO_REQUEST | KEY_INFO_ERROR) > 0 {
+            return None;
+        }
+
+        let message = match (info & KEY_INFO_ACK > 0, info & KEY_INFO_MIC > 0) {
+            (true, false) => HandshakeMessage::M1,
+            (true, true) => HandshakeMessage::M3,
+            (false, false) => return None,
+            // WPA1 doesn't set the Secure bit in M4, but its M2 always carries the WPA element
+            (false, true) if info & KEY_INFO_SECURE == 0 && !self.key_data.is_empty() => {
+                HandshakeMessage::M2
+            }
+            (false, true) => HandshakeMessage::M4,
+        };
+        Some(message)
+    }
+
+    // PMKID KDE, sent by some access points in M1
+    pub fn pmkid(&self) -> Option<[u8; 16]> {
+        if self.key_information & KEY_INFO_ENCRYPTED_KEY_DATA > 0 {
+            return None;
+        }
+
+        let mut reader = Reader::new(&self.key_data);
+        while let (Ok(id), Ok(len)) = (reader.u8(), reader.u8()) {
+            let data = reader.take(len as usize).ok()?;
+            if id == KDE_TYPE && data.len() >= 20 && data[..4] == KDE_PMKID {
+                let mut pmkid = [0; 16];
+                pmkid.copy_from_slice(&data[4..20]);
+                if pmkid.iter().any(|b| *b > 0) {
+                    return Some(pmkid);
+                }
+            }
+        }
+        None
+    }
+
+    // The EAPOL packet with the MIC set to zero, the input of the MIC computation
+    pub fn eapol_with_zero_mic(&self) -> Vec<u8> {
+        let mut key = self.clone();
+        key.mic = vec![0; self.mic.len()];
+        key.to_bytes()[LLC_SNAP_EAPOL.len()..].to_vec()
+    }
+
+    // The Key MIC length isn't encoded in the frame, it depends on the AKM. Guessed
+    // from the Key Data Length that follows it.
+    fn mic_len(fields: &[u8]) -> usize {
+        let fits = |mic_len: usize| match fields.get(mic_len..mic_len + 2) {
+            Some(len) => {
+                usize::from(u16::from_be_bytes([len[0], len[1]])) + mic_len + 2 == fields.len()
+            }
+            None => false,
+        };
+        if !fits(MIC_LEN) && fits(MIC_LEN_SHA384) {
+            MIC_LEN_SHA384
+        } else {
+            MIC_LEN
+        }
+    }
+}
+
+impl Info for EapolKey {
+    fn from_bytes(input: &[u8]) -> Result<EapolKey> {
+        let mut reader = Reader::new(input);
+        reader.take(LLC_SNAP_EAPOL.len())?;
+
+        let version = reader.u8()?;
+        reader.u8()?; // packet type
+        let body_len = u16::from_be_bytes(reader.array()?) as usize;
+        // Anything after the EAPOL packet is padding
+        let mut reader = Reader::new(reader.take(body_len)?);
+
+        let descriptor_type = reader.u8()?;
+        let key_information = u16::from_be_bytes(reader.array()?);
+        let key_length = u16::from_be_bytes(reader.array()?);
+        let replay_counter = u64::from_be_bytes(reader.array()?);
+        let nonce = reader.array()?;
+        let iv = reader.array()?;
+        let rsc = reader.array()?;
+        let reserved = reader.array()?;
+        let mic = reader.take(EapolKey::mic_len(reader.rest()))?.to_vec();
+        let key_data_len = u16::from_be_bytes(reader.array()?) as usize;
+        let key_data = reader.take(key_data_len)?.to_vec();
+
+        Ok(EapolKey {
+            version,
+            descriptor_type,
+            key_information,
+            key_length,
+            replay_counter,
+            nonce,
+            iv,
+            rsc,
+            reserved,
+            mic,
+            key_data,
+        })
+    }
+}
+
+impl Encode for EapolKey {
+    fn encode(&self, writer: &mut Writer) {
+        let body_len = KEY_FIXED_LEN + self.mic.len() + 2 + self.key_data.len();
+
+        writer.bytes(&LLC_SNAP_EAPOL);
+        writer.u8(self.version);
+        writer.u8(EAPOL_KEY);
+        writer.bytes(&(body_len as u16).to_be_bytes());
+        writer.u8(self.descriptor_type);
+        writer.bytes(&self.key_information.to_be_bytes());
+        writer.bytes(&self.key_length.to_be_bytes());
+        writer.bytes(&self.replay_counter.to_be_bytes());
+        writer.bytes(&self.nonce);
+        writer.bytes(&self.iv);
+        writer.bytes(&self.rsc);
+        writer.bytes(&self.reserved);
+        writer.bytes(&self.mic);
+        writer.bytes(&(self.key_data.len() as u16).to_be_bytes());
+        writer.bytes(&self.key_data);
+    }
+}
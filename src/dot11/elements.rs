@@ -0,0 +1,294 @@
+use crate::dot11::info::*;
+use crate::dot11::phy::*;
+use crate::dot11::security::*;
+use crate::dot11::writer::{Encode, Writer};
+
+// Element IDs, IEEE 802.11-2020 9.4.2
+pub const SSID_ELEMENT: u8 = 0;
+pub const SUPPORTED_RATES_ELEMENT: u8 = 1;
+pub const DS_PARAMETER_SET_ELEMENT: u8 = 3;
+pub const COUNTRY_ELEMENT: u8 = 7;
+pub const HT_CAPABILITIES_ELEMENT: u8 = 45;
+pub const RSN_ELEMENT: u8 = 48;
+pub const EXTENDED_SUPPORTED_RATES_ELEMENT: u8 = 50;
+pub const HT_OPERATION_ELEMENT: u8 = 61;
+pub const VHT_CAPABILITIES_ELEMENT: u8 = 191;
+pub const VHT_OPERATION_ELEMENT: u8 = 192;
+pub const VENDOR_SPECIFIC_ELEMENT: u8 = 221;
+pub const FRAGMENT_ELEMENT: u8 = 242;
+pub const EXTENSION_ELEMENT: u8 = 255;
+
+// Element ID Extensions
+pub const HE_CAPABILITIES_EXTENSION: u8 = 35;
+pub const HE_OPERATION_EXTENSION: u8 = 36;
+pub const EHT_OPERATION_EXTENSION: u8 = 106;
+pub const EHT_CAPABILITIES_EXTENSION: u8 = 108;
+
+// Largest value of the length byte
+pub const MAX_ELEMENT_LEN: usize = 255;
+
+// Rates above 54 Mbps are BSS membership selectors (HT PHY, VHT PHY, SAE H2E, ...)
+const MAX_RATE: u8 = 0x6c;
+
+// A raw tagged parameter: `data` excludes the Id, Len and Element Id Extension
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Element<'a> {
+    pub id: u8,
+    pub ext_id: Option<u8>,
+    pub data: &'a [u8],
+}
+
+impl<'a> Element<'a> {
+    pub fn decode(&self) -> InformationElement {
+        match (self.id, self.ext_id) {
+            (SSID_ELEMENT, None) => InformationElement::Ssid(SSID {
+                element_id: self.id,
+                ssid_len: self.data.len(),
+                value: String::from_utf8(self.data.to_vec()).unwrap_or_else(|_| "".to_string()),
+            }),
+            (SUPPORTED_RATES_ELEMENT, None) => {
+                InformationElement::SupportedRates(decode_rates(self.data))
+            }
+            (DS_PARAMETER_SET_ELEMENT, None) if !self.data.is_empty() => {
+                InformationElement::DsParameterSet(self.data[0])
+            }
+            (COUNTRY_ELEMENT, None) => match Country::from_bytes(self.data) {
+                Ok(country) => InformationElement::Country(country),
+                Err(_) => self.unknown(),
+            },
+            (EXTENDED_SUPPORTED_RATES_ELEMENT, None) => {
+                InformationElement::ExtendedSupportedRates(decode_rates(self.data))
+            }
+            (RSN_ELEMENT, None) => match RsnInfo::from_bytes(self.data) {
+                Ok(rsn) => InformationElement::Rsn(rsn),
+                Err(_) => self.unknown(),
+            },
+            (HT_CAPABILITIES_ELEMENT, None) => match HtCapabilities::from_bytes(self.data) {
+                Ok(ht) => InformationElement::HtCapabilities(ht),
+                Err(_) => self.unknown(),
+            },
+            (HT_OPERATION_ELEMENT, None) => match HtOperation::from_bytes(self.data) {
+                Ok(ht) => InformationElement::HtOperation(ht),
+                Err(_) => self.unknown(),
+            },
+            (VHT_CAPABILITIES_ELEMENT, None) => match VhtCapabilities::from_bytes(self.data) {
+                Ok(vht) => InformationElement::VhtCapabilities(vht),
+                Err(_) => self.unknown(),
+            },
+            (VHT_OPERATION_ELEMENT, None) => match VhtOperation::from_bytes(self.data) {
+                Ok(vht) => InformationElement::VhtOperation(vht),
+                Err(_) => self.unknown(),
+            },
+            (EXTENSION_ELEMENT, Some(HE_CAPABILITIES_EXTENSION)) => {
+                match HeCapabilities::from_bytes(self.data) {
+                    Ok(he) => InformationElement::HeCapabilities(he),
+                    Err(_) => self.unknown(),
+                }
+            }
+            (EXTENSION_ELEMENT, Some(HE_OPERATION_EXTENSION)) => {
+                match HeOperation::from_bytes(self.data) {
+                    Ok(he) => InformationElement::HeOperation(he),
+                    Err(_) => self.unknown(),
+                }
+            }
+            (EXTENSION_ELEMENT, Some(EHT_CAPABILITIES_EXTENSION)) => {
+                match EhtCapabilities::from_bytes(self.data) {
+                    Ok(eht) => InformationElement::EhtCapabilities(eht),
+                    Err(_) => self.unknown(),
+                }
+            }
+            (EXTENSION_ELEMENT, Some(EHT_OPERATION_EXTENSION)) => {
+                match EhtOperation::from_bytes(self.data) {
+                    Ok(eht) => InformationElement::EhtOperation(eht),
+                    Err(_) => self.unknown(),
+                }
+            }
+            (VENDOR_SPECIFIC_ELEMENT, None) => match RsnInfo::from_wpa_vendor_bytes(self.data) {
+                Some(wpa) => InformationElement::Wpa(wpa),
+                None => self.unknown(),
+            },
+            _ => self.unknown(),
+        }
+    }
+
+    // Value of the length byte, the Element Id Extension included
+    fn len(&self) -> usize {
+        self.data.len() + self.ext_id.map_or(0, |_| 1)
+    }
+
+    fn unknown(&self) -> InformationElement {
+        InformationElement::Unknown {
+            id: self.id,
+            ext_id: self.ext_id,
+            data: self.data.to_vec(),
+        }
+    }
+}
+
+// Iterates over the tagged parameters of a management frame body.
+// The iteration stops at the first element that doesn't fit in the input.
+#[derive(Clone, Debug)]
+pub struct Elements<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> Elements<'a> {
+    pub fn new(input: &'a [u8]) -> Elements<'a> {
+        Elements { input }
+    }
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = Element<'a>;
+
+    fn next(&mut self) -> Option<Element<'a>> {
+        if self.input.len() < 2 {
+            return None;
+        }
+
+        let id = self.input[0];
+        let len = self.input[1] as usize;
+        if self.input.len() < len + 2 {
+            self.input = &[];
+            return None;
+        }
+
+        let data = &self.input[2..len + 2];
+        self.input = &self.input[len + 2..];
+
+        if id == EXTENSION_ELEMENT && !data.is_empty() {
+            Some(Element {
+                id,
+                ext_id: Some(data[0]),
+                data: &data[1..],
+            })
+        } else {
+            Some(Element {
+                id,
+                ext_id: None,
+                data,
+            })
+        }
+    }
+}
+
+// Decodes the tagged parameters. An element filling its length byte continues in the
+// Fragment elements that follow it, IEEE 802.11-2020 10.28.11.
+pub fn decode_elements(input: &[u8]) -> Vec<InformationElement> {
+    let mut decoded = Vec::new();
+    let mut elements = Elements::new(input).peekable();
+
+    while let Some(element) = elements.next() {
+        if element.len() < MAX_ELEMENT_LEN {
+            decoded.push(element.decode());
+            continue;
+        }
+
+        let mut data = element.data.to_vec();
+        while let Some(fragment) = elements.next_if(|next| next.id == FRAGMENT_ELEMENT) {
+            data.extend_from_slice(fragment.data);
+            if fragment.data.len() < MAX_ELEMENT_LEN {
+                break;
+            }
+        }
+        decoded.push(
+            Element {
+                data: &data,
+                ..element
+            }
+            .decode(),
+        );
+    }
+    decoded
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InformationElement {
+    Ssid(SSID),
+    SupportedRates(Vec<f32>),
+    DsParameterSet(u8), // current channel
+    Country(Country),
+    ExtendedSupportedRates(Vec<f32>),
+    Rsn(RsnInfo),
+    Wpa(RsnInfo), // Microsoft WPA vendor specific element
+    HtCapabilities(HtCapabilities),
+    HtOperation(HtOperation),
+    VhtCapabilities(VhtCapabilities),
+    VhtOperation(VhtOperation),
+    HeCapabilities(HeCapabilities),
+    HeOperation(HeOperation),
+    EhtCapabilities(EhtCapabilities),
+    EhtOperation(EhtOperation),
+    Unknown {
+        id: u8,
+        ext_id: Option<u8>,
+        data: Vec<u8>,
+    },
+}
+
+// Writes the whole element, Id and Len included
+impl Encode for InformationElement {
+    fn encode(&self, writer: &mut Writer) {
+        match self {
+            InformationElement::Ssid(ssid) => ssid.encode(writer),
+            InformationElement::SupportedRates(rates) => {
+                writer.element(SUPPORTED_RATES_ELEMENT, &encode_rates(rates))
+            }
+            InformationElement::DsParameterSet(channel) => {
+                writer.element(DS_PARAMETER_SET_ELEMENT, &[*channel])
+            }
+            InformationElement::Country(country) => {
+                writer.element(COUNTRY_ELEMENT, &country.to_bytes())
+            }
+            InformationElement::ExtendedSupportedRates(rates) => {
+                writer.element(EXTENDED_SUPPORTED_RATES_ELEMENT, &encode_rates(rates))
+            }
+            InformationElement::Rsn(rsn) => writer.element(RSN_ELEMENT, &rsn.to_bytes()),
+            InformationElement::Wpa(wpa) => {
+                writer.element(VENDOR_SPECIFIC_ELEMENT, &wpa.to_wpa_vendor_bytes())
+            }
+            InformationElement::HtCapabilities(ht) => {
+                writer.element(HT_CAPABILITIES_ELEMENT, &ht.to_bytes())
+            }
+            InformationElement::HtOperation(ht) => {
+                writer.element(HT_OPERATION_ELEMENT, &ht.to_bytes())
+            }
+            InformationElement::VhtCapabilities(vht) => {
+                writer.element(VHT_CAPABILITIES_ELEMENT, &vht.to_bytes())
+            }
+            InformationElement::VhtOperation(vht) => {
+                writer.element(VHT_OPERATION_ELEMENT, &vht.to_bytes())
+            }
+            InformationElement::HeCapabilities(he) => {
+                writer.extension_element(HE_CAPABILITIES_EXTENSION, &he.to_bytes())
+            }
+            InformationElement::HeOperation(he) => {
+                writer.extension_element(HE_OPERATION_EXTENSION, &he.to_bytes())
+            }
+            InformationElement::EhtCapabilities(eht) => {
+                writer.extension_element(EHT_CAPABILITIES_EXTENSION, &eht.to_bytes())
+            }
+            InformationElement::EhtOperation(eht) => {
+                writer.extension_element(EHT_OPERATION_EXTENSION, &eht.to_bytes())
+            }
+            InformationElement::Unknown { id, ext_id, data } => match ext_id {
+                Some(ext_id) => writer.extension_element(*ext_id, data),
+                None => writer.element(*id, data),
+            },
+        }
+    }
+}
+
+// Rates are in units of 500 kbps, the most significant bit flags a basic rate
+pub fn decode_rates(data: &[u8]) -> Vec<f32> {
+    data.iter()
+        .map(|rate| rate & 0x7f)
+        .filter(|rate| *rate > 0 && *rate <= MAX_RATE)
+        .map(|rate| f32::from(rate) / 2.0)
+        .collect()
+}
+
+// Basic rate flags aren't kept when decoding, all rates are written as non-basic
+pub fn encode_rates(rates: &[f32]) -> Vec<u8> {
+    rates.iter().map(|rate| (rate * 2.0) as u8 & 0x7f).collect()
+}
@@ -1,8 +1,15 @@
 use crate::util::*;
 use crate::errors::*;
-use bytes::{Buf, IntoBuf, Bytes};
-use std::io::{Cursor, self};
+use crate::dot11::auth::*;
+use crate::dot11::control::*;
+use crate::dot11::eapol::EapolKey;
 use crate::dot11::info::*;
+use crate::dot11::mac::MacAddr;
+use crate::dot11::qos::*;
+use crate::dot11::reader::Reader;
+use crate::dot11::writer::{Encode, Writer};
+
+const MESH_ADDRESS_EXTENSION_MODE: u8 = 0b0000_0011;
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum FrameType {
@@ -34,108 +41,254 @@ pub enum FrameSubType {
     CfPull,
     CfAckCfPull,
     QoS,
+    QoSCfAck,
     QoSCfPull,
     QoSCfAckCfPull,
     QoSNullData,
+    Trigger,
+    Tack,
+    BeamformingReportPoll,
+    NdpAnnouncement,
+    ControlFrameExtension,
+    ControlWrapper,
+    BlockAckReq,
+    BlockAck,
+    PsPoll,
+    Rts,
+    Cts,
+    Ack,
+    CfEnd,
+    CfEndCfAck,
     Reserved,
     UnHandled,
 }
 
+// Addresses that the frame doesn't carry are left unspecified (00:00:00:00:00:00).
+//
+// receiver/transmitter are the stations at both ends of the wireless hop,
+// dst/src the final destination and original source of the frame and bssid
+// the BSS it belongs to. In WDS and mesh frames, which don't carry a BSSID,
+// bssid is the transmitter.
 #[derive(Clone, Debug)]
 pub struct Dot11Header {
     pub frame_control: FrameControl,
     pub duration: [u8; 2],
-    pub dst: String,
-    pub src: String,
-    pub bssid: String,
+    pub receiver: MacAddr,
+    pub transmitter: MacAddr,
+    pub dst: MacAddr,
+    pub src: MacAddr,
+    pub bssid: MacAddr,
     pub seq_ctl: [u8; 2],
+    pub qos: Option<QosControl>,
+    pub ht_control: Option<HtControl>,
+    pub mesh_control: Option<MeshControl>,
     pub info: BodyInformation,
 }
 
 impl Dot11Header {
     pub fn from_bytes(input: &[u8]) -> Result<Dot11Header> {
-        use std::io::Read;
-
-        let buf = Bytes::from(input).into_buf();
-        let mut reader = buf.reader();
-
-        let mut control = [0; 2];
-        reader.read(&mut control)?;
-        let frame_control = FrameControl::from_bytes(&control)?;
+        let mut reader = Reader::new(input);
 
-        let mut duration = [0; 2];
-        reader.read(&mut duration)?;
+        let frame_control = FrameControl::from_bytes(reader.take(2)?)?;
+        let duration = reader.array()?;
+        let (addresses, seq_ctl) = FrameAddresses::read(&mut reader, frame_control)?;
 
-        let mut mac_addresses = [0; 18];
-        reader.read(&mut mac_addresses)?;
-
-        let (dst, src, bssid) = Dot11Header::parse_address(frame_control, &mac_addresses);
+        let mut qos = None;
+        if frame_control.has_qos_control() {
+            qos = Some(QosControl::from_bytes(reader.take(2)?)?);
+        }
+        let mut ht_control = None;
+        if frame_control.has_ht_control() {
+            ht_control = Some(HtControl::from_bytes(reader.take(4)?)?);
+        }
 
-        let mut seq_ctl = [0; 2];
-        reader.read(&mut seq_ctl)?;
+        // Only 4 address frames are told apart as mesh frames, the Mesh Control Present
+        // bit is part of the TXOP limit in frames sent by non mesh stations.
+        let mut mesh_control = None;
+        if addresses.addr4.is_some() && qos.is_some_and(|qos| qos.mesh_control_present()) {
+            mesh_control = Some(MeshControl::read(&mut reader)?);
+        }
 
-        let mut dst2 = vec![];
-        io::copy(&mut reader, &mut dst2)?;
+        let (receiver, transmitter, dst, src, bssid) =
+            Dot11Header::parse_address(frame_control, &addresses);
 
-        let body_information = Dot11Header::parse_body(frame_control, &dst2[..]);
+        let body_information = Dot11Header::parse_body(frame_control, reader.rest())?;
 
         let header = Dot11Header {
             frame_control,
             duration,
+            receiver,
+            transmitter,
             dst,
             src,
             bssid,
             seq_ctl,
+            qos,
+            ht_control,
+            mesh_control,
             info: body_information,
         };
         Ok(header)
     }
 
-    fn parse_address(frame_control: FrameControl, input: &[u8]) -> (String, String, String) {
-        let mut dst = String::from("");
-        let mut src = String::from("");
-        let mut bssid = String::from("");
-
-        let addresses = FrameAddresses::from_bytes(input).unwrap();
-
-        if frame_control.to_ds && frame_control.from_ds {
-            dst.push_str(&addresses.addr3.addr);
-            src.push_str(&addresses.addr4.addr);
-        } else if frame_control.to_ds {
-            dst.push_str(&addresses.addr2.addr);
-            src.push_str(&addresses.addr3.addr);
-            bssid.push_str(&addresses.addr1.addr);
-        } else if frame_control.from_ds {
-            dst.push_str(&addresses.addr3.addr);
-            src.push_str(&addresses.addr1.addr);
-            bssid.push_str(&addresses.addr2.addr);
-        } else {
-            dst.push_str(&addresses.addr1.addr);
-            src.push_str(&addresses.addr2.addr);
-            bssid.push_str(&addresses.addr3.addr);
+    // IEEE 802.11-2020 9.3.2.1, Table 9-30 for data frames
+    fn parse_address(
+        frame_control: FrameControl, addresses: &FrameAddresses,
+    ) -> (MacAddr, MacAddr, MacAddr, MacAddr, MacAddr) {
+        let addr1 = addresses.addr1;
+        let mut addr2 = addresses.addr2.unwrap_or_default();
+        // RTS and CF-End set the I/G bit of the TA to signal the channel bandwidth
+        if matches!(
+            frame_control.frame_subtype,
+            FrameSubType::Rts | FrameSubType::CfEnd | FrameSubType::CfEndCfAck
+        ) {
+            addr2 = addr2.to_individual();
+        }
+        let addr3 = addresses.addr3.unwrap_or_default();
+        let addr4 = addresses.addr4.unwrap_or_default();
+
+        let (dst, src, bssid) = match frame_control.frame_type {
+            FrameType::Control => (
+                addr1,
+                addr2,
+                Dot11Header::control_bssid(frame_control, addr1, addr2),
+            ),
+            FrameType::Data if frame_control.to_ds && frame_control.from_ds => {
+                (addr3, addr4, addr2)
+            }
+            FrameType::Data if frame_control.to_ds => (addr3, addr2, addr1),
+            FrameType::Data if frame_control.from_ds => (addr1, addr3, addr2),
+            _ => (addr1, addr2, addr3),
+        };
+
+        (addr1, addr2, dst, src, bssid)
+    }
+
+    fn control_bssid(frame_control: FrameControl, addr1: MacAddr, addr2: MacAddr) -> MacAddr {
+        match frame_control.frame_subtype {
+            FrameSubType::PsPoll => addr1,
+            FrameSubType::CfEnd | FrameSubType::CfEndCfAck => addr2,
+            _ => MacAddr::UNSPECIFIED,
         }
+    }
 
-        (dst, src, bssid)
+    // PS-Poll frames carry the Association ID in place of the duration
+    pub fn association_id(&self) -> Option<u16> {
+        match self.frame_control.frame_subtype {
+            FrameSubType::PsPoll => Some(u16::from_le_bytes(self.duration) & 0x3fff),
+            _ => None,
+        }
     }
 
-    fn parse_body(frame_control: FrameControl, input: &[u8]) -> BodyInformation {
-        match frame_control.frame_type {
+    fn parse_body(frame_control: FrameControl, input: &[u8]) -> Result<BodyInformation> {
+        let body = match frame_control.frame_type {
             FrameType::Management => {
                 if frame_control.frame_subtype == FrameSubType::Beacon {
-                    BodyInformation::Beacon(Beacon::from_bytes(input))
+                    BodyInformation::Beacon(Beacon::from_bytes(input)?)
                 } else if frame_control.frame_subtype == FrameSubType::ProbeReq {
-                    BodyInformation::ProbeRequest(ProbeRequest::from_bytes(input))
+                    BodyInformation::ProbeRequest(ProbeRequest::from_bytes(input)?)
                 } else if frame_control.frame_subtype == FrameSubType::ProbeResp {
-                    BodyInformation::ProbeResponse(ProbeResponse::from_bytes(input))
+                    BodyInformation::ProbeResponse(ProbeResponse::from_bytes(input)?)
                 } else if frame_control.frame_subtype == FrameSubType::AssoReq {
-                    BodyInformation::AssociationRequest(AssociationRequest::from_bytes(input))
+                    BodyInformation::AssociationRequest(AssociationRequest::from_bytes(input)?)
                 } else if frame_control.frame_subtype == FrameSubType::AssoResp {
-                    BodyInformation::AssociationResponse(AssociationResponse::from_bytes(input))
+                    BodyInformation::AssociationResponse(AssociationResponse::from_bytes(input)?)
+                } else if frame_control.frame_subtype == FrameSubType::ReassoReq {
+                    BodyInformation::ReassociationRequest(ReassociationRequest::from_bytes(input)?)
+                } else if frame_control.frame_subtype == FrameSubType::ReassoResp {
+                    BodyInformation::ReassociationResponse(AssociationResponse::from_bytes(input)?)
+                } else if frame_control.wep {
+                    // Robust management frames (802.11w) and the Shared Key challenge response
+                    BodyInformation::UnHandled(true)
+                } else if frame_control.frame_subtype == FrameSubType::Auth {
+                    BodyInformation::Authentication(Authentication::from_bytes(input)?)
+                } else if frame_control.frame_subtype == FrameSubType::Deauth {
+                    BodyInformation::Deauthentication(Deauthentication::from_bytes(input)?)
+                } else if frame_control.frame_subtype == FrameSubType::Disasso {
+                    BodyInformation::Disassociation(Deauthentication::from_bytes(input)?)
                 } else {
                     BodyInformation::UnHandled(true)
                 }
             }
+            FrameType::Control => match frame_control.frame_subtype {
+                FrameSubType::BlockAckReq => {
+                    BodyInformation::BlockAckRequest(BlockAckRequest::from_bytes(input)?)
+                }
+                FrameSubType::BlockAck => BodyInformation::BlockAck(BlockAck::from_bytes(input)?),
+                _ => BodyInformation::UnHandled(true),
+            },
+            // Handshake frames are sent in the clear, anything protected is opaque
+            FrameType::Data if !frame_control.wep && EapolKey::is_eapol_key(input) => {
+                BodyInformation::EapolKey(EapolKey::from_bytes(input)?)
+            }
             _ => BodyInformation::UnHandled(true),
+        };
+
+        Ok(body)
+    }
+}
+
+impl Encode for Dot11Header {
+    fn encode(&self, writer: &mut Writer) {
+        let addresses = self.addresses();
+
+        self.frame_control.encode(writer);
+        writer.bytes(&self.duration);
+        writer.bytes(&addresses.addr1.octets());
+        for addr in [&addresses.addr2, &addresses.addr3]
+            .iter()
+            .copied()
+            .flatten()
+        {
+            writer.bytes(&addr.octets());
+        }
+        if self.frame_control.frame_type != FrameType::Control {
+            writer.bytes(&self.seq_ctl);
+        }
+        if let Some(addr4) = addresses.addr4 {
+            writer.bytes(&addr4.octets());
+        }
+        if self.frame_control.has_qos_control() {
+            match self.qos {
+                Some(ref qos) => qos.encode(writer),
+                None => writer.u16(0),
+            }
+        }
+        if self.frame_control.has_ht_control() {
+            match self.ht_control {
+                Some(ref ht_control) => ht_control.encode(writer),
+                None => writer.u32(0),
+            }
+        }
+        if let Some(ref mesh_control) = self.mesh_control {
+            mesh_control.encode(writer);
+        }
+        self.info.encode(writer);
+    }
+}
+
+impl Dot11Header {
+    // Inverse of parse_address
+    fn addresses(&self) -> FrameAddresses {
+        let fc = self.frame_control;
+        let transmitter = Some(self.transmitter);
+
+        let (addr2, addr3, addr4) = match fc.frame_type {
+            FrameType::Control if fc.has_transmitter() => (transmitter, None, None),
+            FrameType::Control => (None, None, None),
+            FrameType::Data if fc.to_ds && fc.from_ds => {
+                (transmitter, Some(self.dst), Some(self.src))
+            }
+            FrameType::Data if fc.to_ds => (transmitter, Some(self.dst), None),
+            FrameType::Data if fc.from_ds => (transmitter, Some(self.src), None),
+            _ => (transmitter, Some(self.bssid), None),
+        };
+
+        FrameAddresses {
+            addr1: self.receiver,
+            addr2,
+            addr3,
+            addr4,
         }
     }
 }
@@ -156,12 +309,13 @@ pub struct FrameControl {
 
 impl FrameControl {
     pub fn from_bytes(input: &[u8]) -> Result<FrameControl> {
-        let mut cursor = Cursor::new(input);
-        let version_type_subtype = cursor.get_u8();
-        let flags = cursor.get_u8();
+        let mut reader = Reader::new(input);
+        let version_type_subtype = reader.u8()?;
+        let flags = reader.u8()?;
 
-        if FrameControl::protocol_version(version_type_subtype) != 0 {
-            bail!("Unknow protocol version");
+        let version = FrameControl::protocol_version(version_type_subtype);
+        if version != 0 {
+            bail!(ErrorKind::InvalidProtocolVersion(version));
         }
 
         let frame_type = FrameControl::frame_type(version_type_subtype);
@@ -169,8 +323,8 @@ impl FrameControl {
         let frame_subtype = match frame_type {
             FrameType::Management => FrameControl::frame_subtype(version_type_subtype),
             FrameType::Data => FrameControl::data_frame_subtype(version_type_subtype),
-            FrameType::Control => FrameControl::frame_subtype(version_type_subtype),
-            FrameType::Unknown => FrameControl::frame_subtype(version_type_subtype),
+            FrameType::Control => FrameControl::control_frame_subtype(version_type_subtype),
+            FrameType::Unknown => FrameSubType::UnHandled,
         };
 
         let fc = FrameControl {
@@ -189,6 +343,35 @@ impl FrameControl {
         Ok(fc)
     }
 
+    // CTS, ACK and Control Wrapper frames only carry the receiver address
+    pub fn has_transmitter(&self) -> bool {
+        !matches!(
+            self.frame_subtype,
+            FrameSubType::Cts | FrameSubType::Ack | FrameSubType::ControlWrapper
+        )
+    }
+
+    pub fn has_addr4(&self) -> bool {
+        self.frame_type == FrameType::Data && self.to_ds && self.from_ds
+    }
+
+    // +HTC frames: QoS data and management frames with the Order bit set
+    pub fn has_ht_control(&self) -> bool {
+        self.order && (self.frame_type == FrameType::Management || self.has_qos_control())
+    }
+
+    pub fn has_qos_control(&self) -> bool {
+        self.frame_type == FrameType::Data
+            && matches!(
+                self.frame_subtype,
+                FrameSubType::QoS
+                    | FrameSubType::QoSCfAck
+                    | FrameSubType::QoSCfPull
+                    | FrameSubType::QoSCfAckCfPull
+                    | FrameSubType::QoSNullData
+            )
+    }
+
     fn protocol_version(packet: u8) -> u8 {
         packet & 0b0000_0011
     }
@@ -219,6 +402,26 @@ impl FrameControl {
         }
     }
 
+    fn control_frame_subtype(packet: u8) -> FrameSubType {
+        match (packet & 0b1111_0000) >> 4 {
+            2 => FrameSubType::Trigger,
+            3 => FrameSubType::Tack,
+            4 => FrameSubType::BeamformingReportPoll,
+            5 => FrameSubType::NdpAnnouncement,
+            6 => FrameSubType::ControlFrameExtension,
+            7 => FrameSubType::ControlWrapper,
+            8 => FrameSubType::BlockAckReq,
+            9 => FrameSubType::BlockAck,
+            10 => FrameSubType::PsPoll,
+            11 => FrameSubType::Rts,
+            12 => FrameSubType::Cts,
+            13 => FrameSubType::Ack,
+            14 => FrameSubType::CfEnd,
+            15 => FrameSubType::CfEndCfAck,
+            _ => FrameSubType::UnHandled,
+        }
+    }
+
     fn data_frame_subtype(packet: u8) -> FrameSubType {
         match (packet & 0b1111_0000) >> 4 {
             0 => FrameSubType::Data,
@@ -230,6 +433,7 @@ impl FrameControl {
             6 => FrameSubType::CfPull,
             7 => FrameSubType::CfAckCfPull,
             8 => FrameSubType::QoS,
+            9 => FrameSubType::QoSCfAck,
             10 => FrameSubType::QoSCfPull,
             11 => FrameSubType::QoSCfAckCfPull,
             12 => FrameSubType::QoSNullData,
@@ -239,61 +443,182 @@ impl FrameControl {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct FrameAddresses {
-    pub addr1: MACField,
-    pub addr2: MACField,
-    pub addr3: MACField,
-    pub addr4: MACField,
+impl Encode for FrameControl {
+    fn encode(&self, writer: &mut Writer) {
+        let frame_type = match self.frame_type {
+            FrameType::Management => 0,
+            FrameType::Control => 1,
+            FrameType::Data => 2,
+            FrameType::Unknown => 3,
+        };
+        let flags = [
+            self.to_ds,
+            self.from_ds,
+            self.more_flag,
+            self.retry,
+            self.pwr_mgmt,
+            self.more_data,
+            self.wep,
+            self.order,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |flags, (bit, set)| flags | ((*set as u8) << bit));
+
+        // Protocol version 0
+        writer.u8((self.frame_subtype.number() << 4) | (frame_type << 2));
+        writer.u8(flags);
+    }
 }
 
-impl FrameAddresses {
-    pub fn from_bytes(s: &[u8]) -> Result<FrameAddresses> {
-        use std::io::Read;
-
-        let buf = Bytes::from(s).into_buf();
-        let mut reader = buf.reader();
+impl FrameSubType {
+    // Subtype number within its frame type, UnHandled uses the value 15, which is
+    // reserved for management and data frames
+    fn number(self) -> u8 {
+        match self {
+            FrameSubType::AssoReq => 0,
+            FrameSubType::AssoResp => 1,
+            FrameSubType::ReassoReq => 2,
+            FrameSubType::ReassoResp => 3,
+            FrameSubType::ProbeReq => 4,
+            FrameSubType::ProbeResp => 5,
+            FrameSubType::Beacon => 8,
+            FrameSubType::Atim => 9,
+            FrameSubType::Disasso => 10,
+            FrameSubType::Auth => 11,
+            FrameSubType::Deauth => 12,
+            FrameSubType::Data => 0,
+            FrameSubType::DataCfAck => 1,
+            FrameSubType::DataCfPull => 2,
+            FrameSubType::DataCfAckCfPull => 3,
+            FrameSubType::NullData => 4,
+            FrameSubType::CfAck => 5,
+            FrameSubType::CfPull => 6,
+            FrameSubType::CfAckCfPull => 7,
+            FrameSubType::QoS => 8,
+            FrameSubType::QoSCfAck => 9,
+            FrameSubType::QoSCfPull => 10,
+            FrameSubType::QoSCfAckCfPull => 11,
+            FrameSubType::QoSNullData => 12,
+            FrameSubType::Trigger => 2,
+            FrameSubType::Tack => 3,
+            FrameSubType::BeamformingReportPoll => 4,
+            FrameSubType::NdpAnnouncement => 5,
+            FrameSubType::ControlFrameExtension => 6,
+            FrameSubType::ControlWrapper => 7,
+            FrameSubType::BlockAckReq => 8,
+            FrameSubType::BlockAck => 9,
+            FrameSubType::PsPoll => 10,
+            FrameSubType::Rts => 11,
+            FrameSubType::Cts => 12,
+            FrameSubType::Ack => 13,
+            FrameSubType::CfEnd => 14,
+            FrameSubType::CfEndCfAck => 15,
+            FrameSubType::Reserved => 13,
+            FrameSubType::UnHandled => 15,
+        }
+    }
+}
 
-        let mut mac_addr1 = [0; 6];
-        reader.read(&mut mac_addr1)?;
-        let addr1 = MACField::from_slice(&mac_addr1);
+#[derive(Copy, Clone, Debug)]
+pub struct FrameAddresses {
+    pub addr1: MacAddr,
+    pub addr2: Option<MacAddr>,
+    pub addr3: Option<MacAddr>,
+    pub addr4: Option<MacAddr>, // only in data frames with both To DS and From DS set
+}
 
-        let mut mac_addr2 = [0; 6];
-        reader.read(&mut mac_addr2)?;
-        let addr2 = MACField::from_slice(&mac_addr2);
+impl FrameAddresses {
+    // `input` starts at Address 1, the Sequence Control field before Address 4 is skipped
+    pub fn from_bytes(input: &[u8], frame_control: FrameControl) -> Result<FrameAddresses> {
+        let mut reader = Reader::new(input);
+        let (addresses, _seq_ctl) = FrameAddresses::read(&mut reader, frame_control)?;
 
-        let mut mac_addr3 = [0; 6];
-        reader.read(&mut mac_addr3)?;
-        let addr3 = MACField::from_slice(&mac_addr3);
+        Ok(addresses)
+    }
 
-        let mut seq_ctl = [0; 2];
-        reader.read(&mut seq_ctl)?;
+    // Reads the addresses and the Sequence Control field, in wire order
+    fn read(reader: &mut Reader, frame_control: FrameControl) -> Result<(FrameAddresses, [u8; 2])> {
+        let addr1 = MacAddr(reader.array()?);
+
+        // Control frames have no Address 3 nor Sequence Control
+        if frame_control.frame_type == FrameType::Control {
+            let addr2 = match frame_control.has_transmitter() {
+                true => Some(MacAddr(reader.array()?)),
+                false => None,
+            };
+            let addresses = FrameAddresses {
+                addr1,
+                addr2,
+                addr3: None,
+                addr4: None,
+            };
+            return Ok((addresses, [0; 2]));
+        }
 
-        let mut mac_addr4 = [0; 6];
-        reader.read(&mut mac_addr4)?;
-        let addr4 = MACField::from_slice(&mac_addr4);
+        let addr2 = Some(MacAddr(reader.array()?));
+        let addr3 = Some(MacAddr(reader.array()?));
+        let seq_ctl = reader.array()?;
+        let addr4 = match frame_control.has_addr4() {
+            true => Some(MacAddr(reader.array()?)),
+            false => None,
+        };
 
-        Ok(FrameAddresses {
+        let addresses = FrameAddresses {
             addr1,
             addr2,
             addr3,
             addr4,
-        })
+        };
+        Ok((addresses, seq_ctl))
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct MACField {
-    pub addr: String,
+// Mesh Control field (IEEE 802.11-2020 9.2.4.7.3), at the start of the body of mesh data frames
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeshControl {
+    pub flags: u8,
+    pub ttl: u8,
+    pub sequence_number: u32,
+    pub addr4: Option<MacAddr>, // Address Extension Mode 1
+    pub addr5: Option<MacAddr>, // Address Extension Mode 2, destination
+    pub addr6: Option<MacAddr>, // Address Extension Mode 2, source
 }
 
-impl MACField {
-    pub fn from_slice(s: &[u8]) -> MACField {
-        let addr = format!(
-            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
-            s[0], s[1], s[2], s[3], s[4], s[5]
-        );
+impl MeshControl {
+    fn read(reader: &mut Reader) -> Result<MeshControl> {
+        let flags = reader.u8()?;
+        let ttl = reader.u8()?;
+        let sequence_number = reader.u32()?;
+
+        let mut mesh_control = MeshControl {
+            flags,
+            ttl,
+            sequence_number,
+            addr4: None,
+            addr5: None,
+            addr6: None,
+        };
+        match flags & MESH_ADDRESS_EXTENSION_MODE {
+            1 => mesh_control.addr4 = Some(MacAddr(reader.array()?)),
+            2 => {
+                mesh_control.addr5 = Some(MacAddr(reader.array()?));
+                mesh_control.addr6 = Some(MacAddr(reader.array()?));
+            }
+            _ => {}
+        }
 
-        MACField { addr }
+        Ok(mesh_control)
+    }
+}
+
+impl Encode for MeshControl {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u8(self.flags);
+        writer.u8(self.ttl);
+        writer.u32(self.sequence_number);
+        for addr in [self.addr4, self.addr5, self.addr6].iter().flatten() {
+            writer.bytes(&addr.octets());
+        }
     }
 }
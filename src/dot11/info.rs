@@ -1,5 +1,13 @@
-use bytes::{Buf, Bytes};
-use std::io::Cursor;
+use crate::errors::*;
+use crate::dot11::auth::*;
+use crate::dot11::control::*;
+use crate::dot11::eapol::EapolKey;
+use crate::dot11::elements::*;
+use crate::dot11::mac::MacAddr;
+use crate::dot11::phy::*;
+use crate::dot11::reader::Reader;
+use crate::dot11::security::*;
+use crate::dot11::writer::{Encode, Writer};
 
 #[derive(Clone, Debug)]
 pub enum BodyInformation {
@@ -8,15 +16,51 @@ pub enum BodyInformation {
     ProbeResponse(ProbeResponse),
     AssociationRequest(AssociationRequest),
     AssociationResponse(AssociationResponse),
+    ReassociationRequest(ReassociationRequest),
+    ReassociationResponse(AssociationResponse),
+    Authentication(Authentication),
+    Deauthentication(Deauthentication),
+    Disassociation(Deauthentication),
+    BlockAckRequest(BlockAckRequest),
+    BlockAck(BlockAck),
+    EapolKey(EapolKey),
     UnHandled(bool),
 }
 
 pub trait Info {
-    fn from_bytes(input: &[u8]) -> Self
+    fn from_bytes(input: &[u8]) -> Result<Self>
     where
         Self: Sized;
 }
 
+// Only the handled bodies are written, UnHandled has no body
+impl Encode for BodyInformation {
+    fn encode(&self, writer: &mut Writer) {
+        match self {
+            BodyInformation::Beacon(beacon) => beacon.encode(writer),
+            BodyInformation::ProbeRequest(probe) => probe.encode(writer),
+            BodyInformation::ProbeResponse(probe) => probe.encode(writer),
+            BodyInformation::AssociationRequest(request) => request.encode(writer),
+            BodyInformation::AssociationResponse(response) => response.encode(writer),
+            BodyInformation::ReassociationRequest(request) => request.encode(writer),
+            BodyInformation::ReassociationResponse(response) => response.encode(writer),
+            BodyInformation::Authentication(auth) => auth.encode(writer),
+            BodyInformation::Deauthentication(deauth) => deauth.encode(writer),
+            BodyInformation::Disassociation(disassoc) => disassoc.encode(writer),
+            BodyInformation::BlockAckRequest(request) => request.encode(writer),
+            BodyInformation::BlockAck(block_ack) => block_ack.encode(writer),
+            BodyInformation::EapolKey(key) => key.encode(writer),
+            BodyInformation::UnHandled(_) => {}
+        }
+    }
+}
+
+fn encode_elements(elements: &[InformationElement], writer: &mut Writer) {
+    for element in elements {
+        element.encode(writer);
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Beacon {
     pub timestamp: u64,
@@ -26,30 +70,60 @@ pub struct Beacon {
     pub supported_rates: Vec<f32>,
     pub current_channel: u8,
     pub country: Country,
+    pub security: Security,
+    pub phy: PhyInfo,
+    pub elements: Vec<InformationElement>,
 }
 
 impl Info for Beacon {
-    fn from_bytes(input: &[u8]) -> Beacon {
-        let mut cursor = Cursor::new(input);
+    fn from_bytes(input: &[u8]) -> Result<Beacon> {
+        let mut reader = Reader::new(input);
+
+        let timestamp = reader.u64()?;
+        let interval = reader.u16()?;
+        let cap_info = reader.u16()?;
+        let params = TaggedParameters::from_bytes(reader.rest())?;
+
+        Ok(Beacon::from_parameters(
+            timestamp, interval, cap_info, params,
+        ))
+    }
+}
+
+// The tagged parameters are written from `elements`, the other fields derive from them
+impl Encode for Beacon {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u64(self.timestamp);
+        writer.u16(self.interval);
+        writer.u16(self.cap_info);
+        encode_elements(&self.elements, writer);
+    }
+}
 
-        let timestamp = cursor.get_u64_le();
-        let interval = cursor.get_u16_le();
-        let cap_info = cursor.get_u16_le();
+impl Beacon {
+    pub fn new(
+        timestamp: u64, interval: u16, cap_info: u16, elements: Vec<InformationElement>,
+    ) -> Beacon {
+        let params = TaggedParameters::from_elements(elements);
+        Beacon::from_parameters(timestamp, interval, cap_info, params)
+    }
 
-        let ssid = SSID::from_bytes(cursor.bytes());
-        cursor.advance(ssid.ssid_len + 2); // 2 accounts for Id + Len
-        let supported_rates = supported_rates(cursor.bytes());
-        cursor.advance(supported_rates.len() + 2); // 2 accounts for Id + Len
-        let info = get_info(cursor.bytes());
+    fn from_parameters(
+        timestamp: u64, interval: u16, cap_info: u16, params: TaggedParameters,
+    ) -> Beacon {
+        let phy = PhyInfo::from_parameters(&params);
 
         Beacon {
             timestamp,
             interval,
             cap_info,
-            ssid,
-            supported_rates,
-            current_channel: info.current_channel,
-            country: info.country,
+            ssid: params.ssid,
+            supported_rates: params.supported_rates,
+            current_channel: params.current_channel,
+            country: params.country,
+            phy,
+            security: Security::new(cap_info, params.rsn, params.wpa),
+            elements: params.elements,
         }
     }
 }
@@ -58,18 +132,33 @@ impl Info for Beacon {
 pub struct ProbeRequest {
     pub ssid: SSID,
     pub supported_rates: Vec<f32>,
+    pub elements: Vec<InformationElement>,
 }
 
 impl Info for ProbeRequest {
-    fn from_bytes(input: &[u8]) -> ProbeRequest {
-        let mut cursor = Cursor::new(input);
+    fn from_bytes(input: &[u8]) -> Result<ProbeRequest> {
+        let params = TaggedParameters::from_bytes(input)?;
 
-        let ssid = SSID::from_bytes(cursor.bytes());
-        cursor.advance(ssid.ssid_len + 2);
+        Ok(ProbeRequest::from_parameters(params))
+    }
+}
+
+impl Encode for ProbeRequest {
+    fn encode(&self, writer: &mut Writer) {
+        encode_elements(&self.elements, writer);
+    }
+}
 
+impl ProbeRequest {
+    pub fn new(elements: Vec<InformationElement>) -> ProbeRequest {
+        ProbeRequest::from_parameters(TaggedParameters::from_elements(elements))
+    }
+
+    fn from_parameters(params: TaggedParameters) -> ProbeRequest {
         ProbeRequest {
-            ssid,
-            supported_rates: supported_rates(cursor.bytes()),
+            ssid: params.ssid,
+            supported_rates: params.supported_rates,
+            elements: params.elements,
         }
     }
 }
@@ -83,30 +172,59 @@ pub struct ProbeResponse {
     pub supported_rates: Vec<f32>,
     pub current_channel: u8,
     pub country: Country,
+    pub security: Security,
+    pub phy: PhyInfo,
+    pub elements: Vec<InformationElement>,
 }
 
 impl Info for ProbeResponse {
-    fn from_bytes(input: &[u8]) -> ProbeResponse {
-        let mut cursor = Cursor::new(input);
+    fn from_bytes(input: &[u8]) -> Result<ProbeResponse> {
+        let mut reader = Reader::new(input);
 
-        let timestamp = cursor.get_u64_le();
-        let interval = cursor.get_u16_le();
-        let cap_info = cursor.get_u16_le();
+        let timestamp = reader.u64()?;
+        let interval = reader.u16()?;
+        let cap_info = reader.u16()?;
+        let params = TaggedParameters::from_bytes(reader.rest())?;
 
-        let ssid = SSID::from_bytes(cursor.bytes());
-        cursor.advance(ssid.ssid_len + 2); // 2 accounts for Id + Len
-        let supported_rates = supported_rates(cursor.bytes());
-        cursor.advance(supported_rates.len() + 2); // 2 accounts for Id + Len
-        let info = get_info(cursor.bytes());
+        Ok(ProbeResponse::from_parameters(
+            timestamp, interval, cap_info, params,
+        ))
+    }
+}
+
+impl Encode for ProbeResponse {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u64(self.timestamp);
+        writer.u16(self.interval);
+        writer.u16(self.cap_info);
+        encode_elements(&self.elements, writer);
+    }
+}
+
+impl ProbeResponse {
+    pub fn new(
+        timestamp: u64, interval: u16, cap_info: u16, elements: Vec<InformationElement>,
+    ) -> ProbeResponse {
+        let params = TaggedParameters::from_elements(elements);
+        ProbeResponse::from_parameters(timestamp, interval, cap_info, params)
+    }
+
+    fn from_parameters(
+        timestamp: u64, interval: u16, cap_info: u16, params: TaggedParameters,
+    ) -> ProbeResponse {
+        let phy = PhyInfo::from_parameters(&params);
 
         ProbeResponse {
             timestamp,
             interval,
             cap_info,
-            ssid,
-            supported_rates,
-            current_channel: info.current_channel,
-            country: info.country,
+            ssid: params.ssid,
+            supported_rates: params.supported_rates,
+            current_channel: params.current_channel,
+            country: params.country,
+            phy,
+            security: Security::new(cap_info, params.rsn, params.wpa),
+            elements: params.elements,
         }
     }
 }
@@ -117,52 +235,167 @@ pub struct AssociationRequest {
     pub interval: u16,
     pub ssid: SSID,
     pub supported_rates: Vec<f32>,
+    pub elements: Vec<InformationElement>,
 }
 
 impl Info for AssociationRequest {
-    fn from_bytes(input: &[u8]) -> AssociationRequest {
-        let mut cursor = Cursor::new(input);
+    fn from_bytes(input: &[u8]) -> Result<AssociationRequest> {
+        let mut reader = Reader::new(input);
 
-        let cap_info = cursor.get_u16_le();
-        let interval = cursor.get_u16_le();
-        let ssid = SSID::from_bytes(cursor.bytes());
-        cursor.advance(ssid.ssid_len + 2);
+        let cap_info = reader.u16()?;
+        let interval = reader.u16()?;
+        let params = TaggedParameters::from_bytes(reader.rest())?;
+
+        Ok(AssociationRequest::from_parameters(
+            cap_info, interval, params,
+        ))
+    }
+}
 
+impl Encode for AssociationRequest {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u16(self.cap_info);
+        writer.u16(self.interval);
+        encode_elements(&self.elements, writer);
+    }
+}
+
+impl AssociationRequest {
+    pub fn new(
+        cap_info: u16, interval: u16, elements: Vec<InformationElement>,
+    ) -> AssociationRequest {
+        let params = TaggedParameters::from_elements(elements);
+        AssociationRequest::from_parameters(cap_info, interval, params)
+    }
+
+    fn from_parameters(
+        cap_info: u16, interval: u16, params: TaggedParameters,
+    ) -> AssociationRequest {
         AssociationRequest {
             cap_info,
             interval,
-            ssid,
-            supported_rates: supported_rates(cursor.bytes()),
+            ssid: params.ssid,
+            supported_rates: params.supported_rates,
+            elements: params.elements,
         }
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct ReassociationRequest {
+    pub cap_info: u16,
+    pub interval: u16,
+    pub current_ap: MacAddr,
+    pub ssid: SSID,
+    pub supported_rates: Vec<f32>,
+    pub elements: Vec<InformationElement>,
+}
+
+impl Info for ReassociationRequest {
+    fn from_bytes(input: &[u8]) -> Result<ReassociationRequest> {
+        let mut reader = Reader::new(input);
+
+        let cap_info = reader.u16()?;
+        let interval = reader.u16()?;
+        let current_ap = MacAddr(reader.array()?);
+        let params = TaggedParameters::from_bytes(reader.rest())?;
+
+        Ok(ReassociationRequest::from_parameters(
+            cap_info, interval, current_ap, params,
+        ))
+    }
+}
+
+impl Encode for ReassociationRequest {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u16(self.cap_info);
+        writer.u16(self.interval);
+        writer.bytes(&self.current_ap.octets());
+        encode_elements(&self.elements, writer);
+    }
+}
+
+impl ReassociationRequest {
+    pub fn new(
+        cap_info: u16, interval: u16, current_ap: MacAddr, elements: Vec<InformationElement>,
+    ) -> ReassociationRequest {
+        let params = TaggedParameters::from_elements(elements);
+        ReassociationRequest::from_parameters(cap_info, interval, current_ap, params)
+    }
+
+    fn from_parameters(
+        cap_info: u16, interval: u16, current_ap: MacAddr, params: TaggedParameters,
+    ) -> ReassociationRequest {
+        ReassociationRequest {
+            cap_info,
+            interval,
+            current_ap,
+            ssid: params.ssid,
+            supported_rates: params.supported_rates,
+            elements: params.elements,
+        }
+    }
+}
+
+// Also used for Reassociation Response frames, which share the same layout
 #[derive(Clone, Debug)]
 pub struct AssociationResponse {
     pub cap_info: u16,
     pub status_code: u16,
     pub association_id: u16,
     pub supported_rates: Vec<f32>,
+    pub elements: Vec<InformationElement>,
 }
 
 impl Info for AssociationResponse {
-    fn from_bytes(input: &[u8]) -> AssociationResponse {
-        let mut cursor = Cursor::new(input);
+    fn from_bytes(input: &[u8]) -> Result<AssociationResponse> {
+        let mut reader = Reader::new(input);
 
-        let cap_info = cursor.get_u16_le();
-        let status_code = cursor.get_u16_le();
-        let association_id = cursor.get_u16_le();
+        let cap_info = reader.u16()?;
+        let status_code = reader.u16()?;
+        let association_id = reader.u16()?;
+        let params = TaggedParameters::from_bytes(reader.rest())?;
 
+        Ok(AssociationResponse::from_parameters(
+            cap_info,
+            status_code,
+            association_id,
+            params,
+        ))
+    }
+}
+
+impl Encode for AssociationResponse {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u16(self.cap_info);
+        writer.u16(self.status_code);
+        writer.u16(self.association_id);
+        encode_elements(&self.elements, writer);
+    }
+}
+
+impl AssociationResponse {
+    pub fn new(
+        cap_info: u16, status_code: u16, association_id: u16, elements: Vec<InformationElement>,
+    ) -> AssociationResponse {
+        let params = TaggedParameters::from_elements(elements);
+        AssociationResponse::from_parameters(cap_info, status_code, association_id, params)
+    }
+
+    fn from_parameters(
+        cap_info: u16, status_code: u16, association_id: u16, params: TaggedParameters,
+    ) -> AssociationResponse {
         AssociationResponse {
             cap_info,
             status_code,
             association_id,
-            supported_rates: supported_rates(cursor.bytes()),
+            supported_rates: params.supported_rates,
+            elements: params.elements,
         }
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
 pub struct SSID {
     pub element_id: u8,
     pub ssid_len: usize,
@@ -170,118 +403,133 @@ pub struct SSID {
 }
 
 impl Info for SSID {
-    fn from_bytes(input: &[u8]) -> SSID {
-        let mut cursor = Cursor::new(input);
+    fn from_bytes(input: &[u8]) -> Result<SSID> {
+        let mut reader = Reader::new(input);
 
-        let element_id = cursor.get_u8();
-        let ssid_len = cursor.get_u8() as usize;
-        let mut buf = Bytes::from(cursor.bytes());
-        let ssid = buf.split_to(ssid_len);
+        let element_id = reader.u8()?;
+        let ssid_len = reader.u8()? as usize;
+        let ssid = reader.take(ssid_len)?;
 
-        SSID {
+        Ok(SSID {
             element_id,
             ssid_len,
             value: String::from_utf8(ssid.to_vec()).unwrap_or_else(|_| "".to_string()),
+        })
+    }
+}
+
+// Writes the whole element, like from_bytes reads it
+impl Encode for SSID {
+    fn encode(&self, writer: &mut Writer) {
+        writer.element(self.element_id, self.value.as_bytes());
+    }
+}
+
+impl SSID {
+    pub fn new(value: &str) -> SSID {
+        SSID {
+            element_id: SSID_ELEMENT,
+            ssid_len: value.len(),
+            value: value.to_string(),
         }
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Country {
     pub country_code: String,
 }
 
 impl Info for Country {
-    fn from_bytes(input: &[u8]) -> Country {
-        let mut buf = Bytes::from(input);
-        let country_code = buf.split_to(3); // Country code has 3 bytes
+    fn from_bytes(input: &[u8]) -> Result<Country> {
+        let mut reader = Reader::new(input);
+        let country_code = reader.take(3)?; // Country code has 3 bytes
                                             // We should include the supported channels
-        Country {
+        Ok(Country {
             country_code: String::from_utf8(country_code.to_vec())
                 .unwrap_or_else(|_| "".to_string()),
-        }
+        })
     }
 }
 
-#[derive(Clone, Debug, Default)]
-pub struct AdditionalInfo {
-    country: Country,
-    current_channel: u8,
-}
-
-impl AdditionalInfo {
-    pub fn new() -> AdditionalInfo {
-        let country = Country {
-            ..Default::default()
-        };
-
-        AdditionalInfo {
-            country,
-            current_channel: 0,
+impl Encode for Country {
+    fn encode(&self, writer: &mut Writer) {
+        let mut country_code = [b' '; 3];
+        for (byte, code) in country_code.iter_mut().zip(self.country_code.bytes()) {
+            *byte = code;
         }
+        writer.bytes(&country_code);
     }
 }
 
-pub fn supported_rates(input: &[u8]) -> Vec<f32> {
-    let mut rates: Vec<f32> = Vec::new();
-    let mut cursor = Cursor::new(input);
-
-    let _element_id = cursor.get_u8();
-    let number_of_rates = cursor.get_u8();
-
-    for _x in 0..number_of_rates {
-        let rate = cursor.get_u8();
-
-        match rate {
-            0x82 => rates.push(1.0),
-            0x84 => rates.push(2.0),
-            0x8b => rates.push(5.5),
-            0x0c => rates.push(6.0),
-            0x12 => rates.push(9.0),
-            0x96 => rates.push(11.0),
-            0x18 => rates.push(12.0),
-            0x24 => rates.push(18.0),
-            0x2c => rates.push(22.0),
-            0x30 => rates.push(24.0),
-            0x42 => rates.push(33.0),
-            0x48 => rates.push(36.0),
-            0x60 => rates.push(48.0),
-            0x6c => rates.push(54.0),
-            _ => continue,
-        }
-    }
-
-    rates
+// The tagged parameters shared by the management frame bodies
+#[derive(Clone, Debug, Default)]
+pub struct TaggedParameters {
+    pub ssid: SSID,
+    pub supported_rates: Vec<f32>,
+    pub current_channel: u8,
+    pub country: Country,
+    pub rsn: Option<RsnInfo>,
+    pub wpa: Option<RsnInfo>,
+    pub ht_capabilities: Option<HtCapabilities>,
+    pub ht_operation: Option<HtOperation>,
+    pub vht_capabilities: Option<VhtCapabilities>,
+    pub vht_operation: Option<VhtOperation>,
+    pub he_capabilities: Option<HeCapabilities>,
+    pub he_operation: Option<HeOperation>,
+    pub eht_capabilities: Option<EhtCapabilities>,
+    pub eht_operation: Option<EhtOperation>,
+    pub elements: Vec<InformationElement>,
 }
 
-pub fn get_info(input: &[u8]) -> AdditionalInfo {
-    let mut cursor = Cursor::new(input);
-    let mut info = AdditionalInfo::new();
-
-    loop {
-        let element_id = cursor.get_u8();
-        let len = cursor.get_u8() as usize;
+impl Info for TaggedParameters {
+    fn from_bytes(input: &[u8]) -> Result<TaggedParameters> {
+        Ok(TaggedParameters::from_elements(decode_elements(input)))
+    }
+}
 
-        // Skipping some fields
-        match element_id {
-            0x02 => cursor.advance(len), // FH Parameter Set
-            0x03 => {
-                // DS Parameter Set
-                info.current_channel = cursor.get_u8();
-            }
-            0x04 => cursor.advance(len), // CF Parameter Set
-            0x05 => cursor.advance(len), // TIM
-            0x06 => cursor.advance(len), // IBSS
-            0x07 => {
-                info.country = Country::from_bytes(cursor.bytes());
-                cursor.advance(len);
-            }
-            0x32..=0x42 => cursor.advance(len), // Can appear before country
-            _ => {
-                break;
+impl TaggedParameters {
+    pub fn from_elements(elements: Vec<InformationElement>) -> TaggedParameters {
+        let mut params = TaggedParameters::default();
+
+        for element in elements {
+            match element {
+                InformationElement::Ssid(ref ssid) => params.ssid = ssid.clone(),
+                InformationElement::SupportedRates(ref rates) => {
+                    params.supported_rates.extend(rates)
+                }
+                InformationElement::ExtendedSupportedRates(ref rates) => {
+                    params.supported_rates.extend(rates)
+                }
+                InformationElement::DsParameterSet(channel) => params.current_channel = channel,
+                InformationElement::Country(ref country) => params.country = country.clone(),
+                InformationElement::Rsn(ref rsn) => params.rsn = Some(rsn.clone()),
+                InformationElement::Wpa(ref wpa) => params.wpa = Some(wpa.clone()),
+                InformationElement::HtCapabilities(ref ht) => {
+                    params.ht_capabilities = Some(ht.clone())
+                }
+                InformationElement::HtOperation(ref ht) => params.ht_operation = Some(ht.clone()),
+                InformationElement::VhtCapabilities(ref vht) => {
+                    params.vht_capabilities = Some(vht.clone())
+                }
+                InformationElement::VhtOperation(ref vht) => {
+                    params.vht_operation = Some(vht.clone())
+                }
+                InformationElement::HeCapabilities(ref he) => {
+                    params.he_capabilities = Some(he.clone())
+                }
+                InformationElement::HeOperation(ref he) => params.he_operation = Some(he.clone()),
+                InformationElement::EhtCapabilities(ref eht) => {
+                    params.eht_capabilities = Some(eht.clone())
+                }
+                InformationElement::EhtOperation(ref eht) => {
+                    params.eht_operation = Some(eht.clone())
+                }
+                _ => {}
             }
+            params.elements.push(element);
         }
-    }
 
-    info
+        params
+    }
 }
@@ -0,0 +1,186 @@
+use crate::errors::*;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::{Serialize, Serializer};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+const GROUP_BIT: u8 = 0x01;
+const LOCAL_BIT: u8 = 0x02;
+
+// 48 bit IEEE MAC address, serialized as "aa:bb:cc:dd:ee:ff"
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl MacAddr {
+    pub const BROADCAST: MacAddr = MacAddr([0xff; 6]);
+    // Reported for the addresses a frame doesn't carry
+    pub const UNSPECIFIED: MacAddr = MacAddr([0; 6]);
+
+    pub fn octets(&self) -> [u8; 6] {
+        self.0
+    }
+
+    pub fn oui(&self) -> [u8; 3] {
+        [self.0[0], self.0[1], self.0[2]]
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        *self == MacAddr::BROADCAST
+    }
+
+    pub fn is_unspecified(&self) -> bool {
+        *self == MacAddr::UNSPECIFIED
+    }
+
+    // Group addresses (I/G bit set), broadcast included
+    pub fn is_group(&self) -> bool {
+        self.0[0] & GROUP_BIT > 0
+    }
+
+    // Same address with the I/G bit cleared
+    pub fn to_individual(self) -> MacAddr {
+        let mut octets = self.0;
+        octets[0] &= !GROUP_BIT;
+        MacAddr(octets)
+    }
+
+    pub fn is_multicast(&self) -> bool {
+        self.is_group() && !self.is_broadcast()
+    }
+
+    pub fn is_unicast(&self) -> bool {
+        !self.is_group()
+    }
+
+    // U/L bit set, e.g. randomized addresses in probe requests
+    pub fn is_locally_administered(&self) -> bool {
+        self.0[0] & LOCAL_BIT > 0
+    }
+
+    pub fn is_universal(&self) -> bool {
+        !self.is_locally_administered()
+    }
+}
+
+impl From<[u8; 6]> for MacAddr {
+    fn from(octets: [u8; 6]) -> MacAddr {
+        MacAddr(octets)
+    }
+}
+
+// The first 6 bytes of the slice
+impl TryFrom<&[u8]> for MacAddr {
+    type Error = Error;
+
+    fn try_from(s: &[u8]) -> Result<MacAddr> {
+        if s.len() < 6 {
+            bail!(ErrorKind::Truncated(6, s.len()));
+        }
+
+        let mut octets = [0; 6];
+        octets.copy_from_slice(&s[..6]);
+        Ok(MacAddr(octets))
+    }
+}
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let o = self.0;
+        write!(
+            f,
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            o[0], o[1], o[2], o[3], o[4], o[5]
+        )
+    }
+}
+
+// Accepts ':' or '-' separated hex octets
+impl FromStr for MacAddr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<MacAddr> {
+        let mut octets = [0; 6];
+        if !parse_octets(s, &mut octets) {
+            bail!(ErrorKind::InvalidMacAddress(s.to_string()));
+        }
+
+        Ok(MacAddr(octets))
+    }
+}
+
+// Fills octets from ':' or '-' separated hex octets, false unless they all match
+fn parse_octets(s: &str, octets: &mut [u8]) -> bool {
+    let parts: Vec<&str> = s.split([':', '-']).collect();
+    if parts.len() != octets.len() || parts.iter().any(|part| part.len() != 2) {
+        return false;
+    }
+
+    for (octet, part) in octets.iter_mut().zip(parts) {
+        match u8::from_str_radix(part, 16) {
+            Ok(value) => *octet = value,
+            Err(_) => return false,
+        }
+    }
+    true
+}
+
+impl Serialize for MacAddr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MacAddr {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<MacAddr, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+// Organizationally Unique Identifier, the first 3 octets of a MAC address.
+// Serialized as "aa:bb:cc".
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Oui(pub [u8; 3]);
+
+impl From<MacAddr> for Oui {
+    fn from(addr: MacAddr) -> Oui {
+        Oui(addr.oui())
+    }
+}
+
+impl fmt::Display for Oui {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let o = self.0;
+        write!(f, "{:02x}:{:02x}:{:02x}", o[0], o[1], o[2])
+    }
+}
+
+// Accepts ':' or '-' separated hex octets
+impl FromStr for Oui {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Oui> {
+        let mut octets = [0; 3];
+        if !parse_octets(s, &mut octets) {
+            bail!(ErrorKind::InvalidOui(s.to_string()));
+        }
+
+        Ok(Oui(octets))
+    }
+}
+
+impl Serialize for Oui {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Oui {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Oui, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
@@ -1,3 +1,13 @@
+pub mod auth;
+pub mod control;
+pub mod eapol;
+pub mod elements;
 pub mod header;
 pub mod info;
+pub mod mac;
+pub mod phy;
+pub mod qos;
+pub mod reader;
+pub mod security;
 pub mod vendors;
+pub mod writer;
@@ -0,0 +1,535 @@
+use crate::errors::*;
+use crate::dot11::info::{Info, TaggedParameters};
+use crate::dot11::reader::Reader;
+use crate::dot11::writer::{Encode, Writer};
+use serde::{Serialize, Deserialize};
+
+const HT_CAP_CHANNEL_WIDTH_40: u16 = 0x0002;
+const HT_CAP_SHORT_GI_20: u16 = 0x0020;
+const HT_CAP_SHORT_GI_40: u16 = 0x0040;
+const HT_OP_ANY_CHANNEL_WIDTH: u8 = 0x04;
+const VHT_CAP_SHORT_GI_80: u32 = 0x0000_0020;
+const VHT_CAP_SHORT_GI_160: u32 = 0x0000_0040;
+const HE_OP_VHT_OPERATION_PRESENT: u32 = 0x0000_4000;
+const HE_OP_CO_HOSTED_BSS: u32 = 0x0000_8000;
+const HE_OP_6GHZ_OPERATION_PRESENT: u32 = 0x0002_0000;
+const EHT_OP_INFORMATION_PRESENT: u8 = 0x01;
+
+// Highest per spatial stream data rate (Mbps) for 20, 40, 80, 160 and 320 MHz
+const HT_RATES: [f32; 2] = [65.0, 135.0];
+const HT_RATES_SHORT_GI: [f32; 2] = [72.2, 150.0];
+const VHT_RATES: [f32; 4] = [78.0, 180.0, 390.0, 780.0];
+const VHT_RATES_SHORT_GI: [f32; 4] = [86.7, 200.0, 433.3, 866.7];
+const HE_RATES: [f32; 4] = [143.4, 286.8, 600.5, 1201.0];
+const EHT_RATES: [f32; 5] = [172.1, 344.1, 720.6, 1441.2, 2882.4];
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SecondaryChannelOffset {
+    #[default]
+    None,
+    Above,
+    Below,
+}
+
+// HT Capabilities element (ID 45)
+#[derive(Clone, Debug, PartialEq)]
+pub struct HtCapabilities {
+    pub cap_info: u16,
+    pub ampdu_params: u8,
+    pub rx_mcs: [u8; 10], // MCS 0-76 bitmask
+    pub spatial_streams: u8,
+}
+
+impl Info for HtCapabilities {
+    fn from_bytes(input: &[u8]) -> Result<HtCapabilities> {
+        let mut reader = Reader::new(input);
+
+        let cap_info = reader.u16()?;
+        let ampdu_params = reader.u8()?;
+        let rx_mcs: [u8; 10] = reader.array()?;
+        // Each of the first 4 bytes holds MCS 0-7 of one spatial stream
+        let spatial_streams = rx_mcs[..4].iter().filter(|mcs| **mcs != 0).count() as u8;
+
+        Ok(HtCapabilities {
+            cap_info,
+            ampdu_params,
+            rx_mcs,
+            spatial_streams,
+        })
+    }
+}
+
+// The fields that aren't decoded are written as zeros
+impl Encode for HtCapabilities {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u16(self.cap_info);
+        writer.u8(self.ampdu_params);
+        writer.bytes(&self.rx_mcs);
+        writer.bytes(&[0; 6]); // highest data rate, Tx MCS parameters
+        writer.u16(0); // extended capabilities
+        writer.u32(0); // transmit beamforming
+        writer.u8(0); // ASEL
+    }
+}
+
+impl HtCapabilities {
+    pub fn supports_40mhz(&self) -> bool {
+        self.cap_info & HT_CAP_CHANNEL_WIDTH_40 > 0
+    }
+
+    pub fn short_gi(&self, width: u16) -> bool {
+        match width {
+            20 => self.cap_info & HT_CAP_SHORT_GI_20 > 0,
+            _ => self.cap_info & HT_CAP_SHORT_GI_40 > 0,
+        }
+    }
+}
+
+// HT Operation element (ID 61)
+#[derive(Clone, Debug, PartialEq)]
+pub struct HtOperation {
+    pub primary_channel: u8,
+    pub secondary_channel_offset: SecondaryChannelOffset,
+    pub any_channel_width: bool,
+}
+
+impl Info for HtOperation {
+    fn from_bytes(input: &[u8]) -> Result<HtOperation> {
+        let mut reader = Reader::new(input);
+
+        let primary_channel = reader.u8()?;
+        let info = reader.u8()?;
+        let secondary_channel_offset = match info & 0b0000_0011 {
+            1 => SecondaryChannelOffset::Above,
+            3 => SecondaryChannelOffset::Below,
+            _ => SecondaryChannelOffset::None,
+        };
+
+        Ok(HtOperation {
+            primary_channel,
+            secondary_channel_offset,
+            any_channel_width: info & HT_OP_ANY_CHANNEL_WIDTH > 0,
+        })
+    }
+}
+
+impl Encode for HtOperation {
+    fn encode(&self, writer: &mut Writer) {
+        let mut info = match self.secondary_channel_offset {
+            SecondaryChannelOffset::None => 0,
+            SecondaryChannelOffset::Above => 1,
+            SecondaryChannelOffset::Below => 3,
+        };
+        if self.any_channel_width {
+            info |= HT_OP_ANY_CHANNEL_WIDTH;
+        }
+
+        writer.u8(self.primary_channel);
+        writer.u8(info);
+        writer.bytes(&[0; 20]); // operation information, basic HT-MCS set
+    }
+}
+
+impl HtOperation {
+    pub fn channel_width(&self) -> u16 {
+        if self.any_channel_width && self.secondary_channel_offset != SecondaryChannelOffset::None {
+            40
+        } else {
+            20
+        }
+    }
+}
+
+// VHT Capabilities element (ID 191)
+#[derive(Clone, Debug, PartialEq)]
+pub struct VhtCapabilities {
+    pub cap_info: u32,
+    pub rx_mcs_map: u16,
+    pub spatial_streams: u8,
+}
+
+impl Info for VhtCapabilities {
+    fn from_bytes(input: &[u8]) -> Result<VhtCapabilities> {
+        let mut reader = Reader::new(input);
+
+        let cap_info = reader.u32()?;
+        let rx_mcs_map = reader.u16()?;
+
+        Ok(VhtCapabilities {
+            cap_info,
+            rx_mcs_map,
+            spatial_streams: spatial_streams(rx_mcs_map),
+        })
+    }
+}
+
+impl Encode for VhtCapabilities {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u32(self.cap_info);
+        writer.u16(self.rx_mcs_map);
+        writer.u16(0);
+        writer.u16(self.rx_mcs_map); // Tx MCS map
+        writer.u16(0);
+    }
+}
+
+impl VhtCapabilities {
+    pub fn short_gi(&self, width: u16) -> bool {
+        match width {
+            160 => self.cap_info & VHT_CAP_SHORT_GI_160 > 0,
+            _ => self.cap_info & VHT_CAP_SHORT_GI_80 > 0,
+        }
+    }
+}
+
+// VHT Operation element (ID 192), also carried inside the HE Operation element
+#[derive(Clone, Debug, PartialEq)]
+pub struct VhtOperation {
+    pub channel_width: u8,
+    pub center_freq_seg0: u8,
+    pub center_freq_seg1: u8,
+}
+
+impl Info for VhtOperation {
+    fn from_bytes(input: &[u8]) -> Result<VhtOperation> {
+        let mut reader = Reader::new(input);
+
+        Ok(VhtOperation {
+            channel_width: reader.u8()?,
+            center_freq_seg0: reader.u8()?,
+            center_freq_seg1: reader.u8()?,
+        })
+    }
+}
+
+impl Encode for VhtOperation {
+    fn encode(&self, writer: &mut Writer) {
+        self.encode_information(writer);
+        writer.u16(0xffff); // basic VHT-MCS and NSS set
+    }
+}
+
+impl VhtOperation {
+    // None when the width is given by the HT Operation element (20 or 40 MHz)
+    pub fn channel_width(&self) -> Option<u16> {
+        match self.channel_width {
+            1 if self.center_freq_seg1 != 0 => Some(160), // 160 or 80+80 MHz
+            1 => Some(80),
+            2 | 3 => Some(160), // deprecated 160 and 80+80 MHz signalling
+            _ => None,
+        }
+    }
+
+    // The 3 bytes that are also carried inside the HE Operation element
+    fn encode_information(&self, writer: &mut Writer) {
+        writer.u8(self.channel_width);
+        writer.u8(self.center_freq_seg0);
+        writer.u8(self.center_freq_seg1);
+    }
+}
+
+// HE Capabilities element (Element ID Extension 35)
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeCapabilities {
+    pub mac_cap: [u8; 6],
+    pub phy_cap: [u8; 11],
+    pub rx_mcs_map: u16, // <= 80 MHz
+    pub spatial_streams: u8,
+}
+
+impl Info for HeCapabilities {
+    fn from_bytes(input: &[u8]) -> Result<HeCapabilities> {
+        let mut reader = Reader::new(input);
+
+        let mac_cap = reader.array()?;
+        let phy_cap = reader.array()?;
+        let rx_mcs_map = reader.u16()?;
+
+        Ok(HeCapabilities {
+            mac_cap,
+            phy_cap,
+            rx_mcs_map,
+            spatial_streams: spatial_streams(rx_mcs_map),
+        })
+    }
+}
+
+impl Encode for HeCapabilities {
+    fn encode(&self, writer: &mut Writer) {
+        writer.bytes(&self.mac_cap);
+        writer.bytes(&self.phy_cap);
+        writer.u16(self.rx_mcs_map);
+        writer.u16(self.rx_mcs_map); // Tx MCS map
+    }
+}
+
+// HE Operation element (Element ID Extension 36)
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeOperation {
+    pub params: u32, // 24 bits
+    pub bss_color: u8,
+    pub vht_operation: Option<VhtOperation>,
+    pub six_ghz_channel_width: Option<u16>,
+}
+
+impl Info for HeOperation {
+    fn from_bytes(input: &[u8]) -> Result<HeOperation> {
+        let mut reader = Reader::new(input);
+
+        let params = reader.take(3)?;
+        let params = u32::from_le_bytes([params[0], params[1], params[2], 0]);
+        let bss_color = reader.u8()?;
+        let _basic_mcs = reader.u16()?;
+
+        let mut vht_operation = None;
+        if params & HE_OP_VHT_OPERATION_PRESENT > 0 {
+            vht_operation = Some(VhtOperation::from_bytes(reader.take(3)?)?);
+        }
+        if params & HE_OP_CO_HOSTED_BSS > 0 {
+            let _max_bssid_indicator = reader.u8()?;
+        }
+
+        let mut six_ghz_channel_width = None;
+        if params & HE_OP_6GHZ_OPERATION_PRESENT > 0 {
+            let _primary_channel = reader.u8()?;
+            let control = reader.u8()?;
+            six_ghz_channel_width = Some(20 << (control & 0b0000_0011));
+        }
+
+        Ok(HeOperation {
+            params,
+            bss_color,
+            vht_operation,
+            six_ghz_channel_width,
+        })
+    }
+}
+
+// The optional fields are flagged from the decoded values, the primary channel of
+// the 6 GHz Operation Information isn't kept and is written as 0
+impl Encode for HeOperation {
+    fn encode(&self, writer: &mut Writer) {
+        let mut params =
+            self.params & !(HE_OP_VHT_OPERATION_PRESENT | HE_OP_6GHZ_OPERATION_PRESENT);
+        if self.vht_operation.is_some() {
+            params |= HE_OP_VHT_OPERATION_PRESENT;
+        }
+        if self.six_ghz_channel_width.is_some() {
+            params |= HE_OP_6GHZ_OPERATION_PRESENT;
+        }
+
+        writer.bytes(&params.to_le_bytes()[..3]);
+        writer.u8(self.bss_color);
+        writer.u16(0xfffc); // basic HE-MCS and NSS set
+        if let Some(ref vht) = self.vht_operation {
+            vht.encode_information(writer);
+        }
+        if params & HE_OP_CO_HOSTED_BSS > 0 {
+            writer.u8(0); // max co-hosted BSSID indicator
+        }
+        if let Some(width) = self.six_ghz_channel_width {
+            writer.u8(0);
+            writer.u8(channel_width_control(width) & 0b0000_0011);
+            writer.bytes(&[0; 3]); // center frequency segments, minimum rate
+        }
+    }
+}
+
+// EHT Capabilities element (Element ID Extension 108)
+#[derive(Clone, Debug, PartialEq)]
+pub struct EhtCapabilities {
+    pub mac_cap: u16,
+    pub phy_cap: [u8; 9],
+}
+
+impl Info for EhtCapabilities {
+    fn from_bytes(input: &[u8]) -> Result<EhtCapabilities> {
+        let mut reader = Reader::new(input);
+
+        let mac_cap = reader.u16()?;
+        let phy_cap = reader.array()?;
+
+        Ok(EhtCapabilities { mac_cap, phy_cap })
+    }
+}
+
+// The EHT-MCS and NSS set isn't decoded and isn't written either
+impl Encode for EhtCapabilities {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u16(self.mac_cap);
+        writer.bytes(&self.phy_cap);
+    }
+}
+
+// EHT Operation element (Element ID Extension 106)
+#[derive(Clone, Debug, PartialEq)]
+pub struct EhtOperation {
+    pub params: u8,
+    pub channel_width: Option<u16>,
+}
+
+impl Info for EhtOperation {
+    fn from_bytes(input: &[u8]) -> Result<EhtOperation> {
+        let mut reader = Reader::new(input);
+
+        let params = reader.u8()?;
+        let _basic_mcs = reader.u32()?;
+
+        let mut channel_width = None;
+        if params & EHT_OP_INFORMATION_PRESENT > 0 {
+            let control = reader.u8()?;
+            channel_width = match control & 0b0000_0111 {
+                width @ 0..=4 => Some(20 << width),
+                _ => None,
+            };
+        }
+
+        Ok(EhtOperation {
+            params,
+            channel_width,
+        })
+    }
+}
+
+impl Encode for EhtOperation {
+    fn encode(&self, writer: &mut Writer) {
+        let mut params = self.params & !EHT_OP_INFORMATION_PRESENT;
+        if self.channel_width.is_some() {
+            params |= EHT_OP_INFORMATION_PRESENT;
+        }
+
+        writer.u8(params);
+        writer.u32(0xffff_fffc); // basic EHT-MCS and NSS set
+        if let Some(width) = self.channel_width {
+            writer.u8(channel_width_control(width));
+            writer.bytes(&[0; 2]); // center frequency segments
+        }
+    }
+}
+
+// Summary of the PHY capabilities advertised by an access point
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PhyInfo {
+    pub standard: String,    // 802.11b/a/g/n/ac/ax/be
+    pub wifi_generation: u8, // Wi-Fi 1 to 7
+    pub channel_width: u16,  // MHz
+    pub secondary_channel_offset: SecondaryChannelOffset,
+    pub spatial_streams: u8,
+    pub max_rate: f32, // Mbps
+}
+
+impl PhyInfo {
+    pub fn from_parameters(params: &TaggedParameters) -> PhyInfo {
+        let he_vht_operation = params
+            .he_operation
+            .as_ref()
+            .and_then(|he| he.vht_operation.as_ref());
+        let channel_width = params
+            .eht_operation
+            .as_ref()
+            .and_then(|eht| eht.channel_width)
+            .or_else(|| {
+                params
+                    .he_operation
+                    .as_ref()
+                    .and_then(|he| he.six_ghz_channel_width)
+            })
+            .or_else(|| {
+                params
+                    .vht_operation
+                    .as_ref()
+                    .and_then(VhtOperation::channel_width)
+            })
+            .or_else(|| he_vht_operation.and_then(VhtOperation::channel_width))
+            .or_else(|| params.ht_operation.as_ref().map(HtOperation::channel_width))
+            .unwrap_or(20);
+
+        let secondary_channel_offset = params
+            .ht_operation
+            .as_ref()
+            .map(|ht| ht.secondary_channel_offset)
+            .unwrap_or_default();
+
+        let spatial_streams = [
+            params.ht_capabilities.as_ref().map(|ht| ht.spatial_streams),
+            params
+                .vht_capabilities
+                .as_ref()
+                .map(|vht| vht.spatial_streams),
+            params.he_capabilities.as_ref().map(|he| he.spatial_streams),
+        ]
+        .iter()
+        .filter_map(|streams| *streams)
+        .max()
+        .unwrap_or(1)
+        .max(1);
+
+        let (standard, wifi_generation, per_stream) = if params.eht_capabilities.is_some() {
+            ("802.11be", 7, rate_for_width(&EHT_RATES, channel_width))
+        } else if params.he_capabilities.is_some() {
+            ("802.11ax", 6, rate_for_width(&HE_RATES, channel_width))
+        } else if let Some(ref vht) = params.vht_capabilities {
+            let rates = if vht.short_gi(channel_width) {
+                &VHT_RATES_SHORT_GI
+            } else {
+                &VHT_RATES
+            };
+            ("802.11ac", 5, rate_for_width(rates, channel_width))
+        } else if let Some(ref ht) = params.ht_capabilities {
+            let rates = if ht.short_gi(channel_width) {
+                &HT_RATES_SHORT_GI
+            } else {
+                &HT_RATES
+            };
+            ("802.11n", 4, rate_for_width(rates, channel_width))
+        } else {
+            let legacy_rate = params.supported_rates.iter().cloned().fold(0.0, f32::max);
+            if params.current_channel > 14 {
+                ("802.11a", 2, legacy_rate)
+            } else if legacy_rate > 11.0 {
+                ("802.11g", 3, legacy_rate)
+            } else {
+                ("802.11b", 1, legacy_rate)
+            }
+        };
+
+        let max_rate = if wifi_generation >= 4 {
+            per_stream * f32::from(spatial_streams)
+        } else {
+            per_stream
+        };
+
+        PhyInfo {
+            standard: standard.into(),
+            wifi_generation,
+            channel_width,
+            secondary_channel_offset,
+            spatial_streams,
+            max_rate,
+        }
+    }
+}
+
+// VHT and HE MCS maps use 2 bits per spatial stream, 3 means not supported
+fn spatial_streams(mcs_map: u16) -> u8 {
+    (0..8)
+        .filter(|ss| (mcs_map >> (ss * 2)) & 0b11 != 0b11)
+        .count() as u8
+}
+
+fn rate_for_width(rates: &[f32], width: u16) -> f32 {
+    let index = match width {
+        0..=20 => 0,
+        21..=40 => 1,
+        41..=80 => 2,
+        81..=160 => 3,
+        _ => 4,
+    };
+
+    rates[index.min(rates.len() - 1)]
+}
+
+// 20, 40, 80, 160 and 320 MHz are signalled as 0 to 4
+fn channel_width_control(width: u16) -> u8 {
+    (width.max(20) / 20).trailing_zeros() as u8
+}
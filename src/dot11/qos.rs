@@ -0,0 +1,180 @@
+use crate::errors::*;
+use crate::dot11::info::Info;
+use crate::dot11::reader::Reader;
+use crate::dot11::writer::{Encode, Writer};
+use serde::{Serialize, Deserialize};
+
+const QOS_EOSP: u16 = 0x0010;
+const QOS_AMSDU_PRESENT: u16 = 0x0080;
+const QOS_MESH_CONTROL_PRESENT: u8 = 0x01; // bit 8, in the high byte
+const HT_CONTROL_VHT: u32 = 0x0000_0001;
+const HT_CONTROL_HE: u32 = 0x0000_0002;
+
+// WMM access categories, from lowest to highest priority
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AccessCategory {
+    Background,
+    BestEffort,
+    Video,
+    Voice,
+}
+
+impl AccessCategory {
+    // User priority to access category (IEEE 802.11-2020 Table 10-1), the traffic
+    // stream TIDs 8-15 are reported as best effort
+    pub fn from_tid(tid: u8) -> AccessCategory {
+        match tid {
+            1 | 2 => AccessCategory::Background,
+            4 | 5 => AccessCategory::Video,
+            6 | 7 => AccessCategory::Voice,
+            _ => AccessCategory::BestEffort,
+        }
+    }
+}
+
+// QoS Control field (IEEE 802.11-2020 9.2.4.5)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct QosControl {
+    pub tid: u8,
+    pub eosp: bool,
+    pub ack_policy: u8,
+    pub amsdu_present: bool,
+    // TXOP limit, queue size, or the Mesh Control Present and mesh power save flags
+    pub high: u8,
+}
+
+impl Info for QosControl {
+    fn from_bytes(input: &[u8]) -> Result<QosControl> {
+        let qos = Reader::new(input).u16()?;
+
+        Ok(QosControl {
+            tid: (qos & 0x000f) as u8,
+            eosp: qos & QOS_EOSP > 0,
+            ack_policy: ((qos >> 5) & 0b11) as u8,
+            amsdu_present: qos & QOS_AMSDU_PRESENT > 0,
+            high: (qos >> 8) as u8,
+        })
+    }
+}
+
+impl Encode for QosControl {
+    fn encode(&self, writer: &mut Writer) {
+        let mut qos = u16::from(self.tid & 0x0f)
+            | u16::from(self.ack_policy & 0b11) << 5
+            | u16::from(self.high) << 8;
+        if self.eosp {
+            qos |= QOS_EOSP;
+        }
+        if self.amsdu_present {
+            qos |= QOS_AMSDU_PRESENT;
+        }
+        writer.u16(qos);
+    }
+}
+
+impl QosControl {
+    pub fn access_category(&self) -> AccessCategory {
+        AccessCategory::from_tid(self.tid)
+    }
+
+    // Only meaningful in frames sent by mesh stations
+    pub fn mesh_control_present(&self) -> bool {
+        self.high & QOS_MESH_CONTROL_PRESENT > 0
+    }
+}
+
+// HE A-Control subfield: a 4 bit Control ID and its Control Information
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AControl {
+    pub id: u8,
+    pub info: u32,
+}
+
+// HT Control field (IEEE 802.11-2020 9.2.4.6), present in +HTC frames
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HtControl {
+    Ht(u32),
+    Vht(u32),
+    He(Vec<AControl>),
+}
+
+impl Info for HtControl {
+    fn from_bytes(input: &[u8]) -> Result<HtControl> {
+        let value = Reader::new(input).u32()?;
+
+        let ht_control = if value & HT_CONTROL_VHT == 0 {
+            HtControl::Ht(value)
+        } else if value & HT_CONTROL_HE == 0 {
+            HtControl::Vht(value)
+        } else {
+            HtControl::He(HtControl::a_control(value >> 2))
+        };
+        Ok(ht_control)
+    }
+}
+
+impl Encode for HtControl {
+    fn encode(&self, writer: &mut Writer) {
+        let value = match self {
+            HtControl::Ht(value) => value & !HT_CONTROL_VHT,
+            HtControl::Vht(value) => (value | HT_CONTROL_VHT) & !HT_CONTROL_HE,
+            HtControl::He(subfields) => {
+                let mut value = 0;
+                let mut offset = 2;
+                for subfield in subfields {
+                    let len = match a_control_len(subfield.id) {
+                        Some(len) if offset + 4 + len <= 32 => len,
+                        _ => break,
+                    };
+                    let info = subfield.info & ((1 << len) - 1);
+                    value |= (u32::from(subfield.id) | info << 4) << offset;
+                    offset += 4 + len;
+                }
+                // Padding: ONES subfield(s)
+                if offset < 32 {
+                    value |= u32::MAX << offset;
+                }
+                value | HT_CONTROL_VHT | HT_CONTROL_HE
+            }
+        };
+        writer.u32(value);
+    }
+}
+
+impl HtControl {
+    // Splits the 30 bit A-Control field, subfields with unknown lengths end the list
+    fn a_control(mut bits: u32) -> Vec<AControl> {
+        let mut subfields = Vec::new();
+        let mut remaining = 30;
+
+        while remaining >= 4 {
+            let id = (bits & 0x0f) as u8;
+            let len = match a_control_len(id) {
+                Some(len) if len + 4 <= remaining => len,
+                _ => break,
+            };
+            subfields.push(AControl {
+                id,
+                info: (bits >> 4) & ((1 << len) - 1),
+            });
+            bits >>= 4 + len;
+            remaining -= 4 + len;
+        }
+
+        subfields
+    }
+}
+
+// Control Information length in bits (IEEE 802.11ax-2021 Table 9-22a)
+fn a_control_len(id: u8) -> Option<u32> {
+    match id {
+        0 => Some(26), // TRS
+        1 => Some(12), // OM
+        2 => Some(26), // HLA
+        3 => Some(26), // BSR
+        4 => Some(8),  // UPH
+        5 => Some(10), // BQR
+        6 => Some(8),  // CAS
+        _ => None,     // ONES padding or unknown
+    }
+}
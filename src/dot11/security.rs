@@ -0,0 +1,426 @@
+use crate::errors::*;
+use crate::dot11::info::Info;
+use crate::dot11::reader::Reader;
+use crate::dot11::writer::{Encode, Writer};
+use serde::{Serialize, Deserialize};
+
+const IEEE_OUI: [u8; 3] = [0x00, 0x0f, 0xac];
+const MICROSOFT_OUI: [u8; 3] = [0x00, 0x50, 0xf2];
+const WPA_OUI_TYPE: u8 = 1;
+const CAP_INFO_PRIVACY: u16 = 0x0010;
+const RSN_CAP_MFP_REQUIRED: u16 = 0x0040;
+const RSN_CAP_MFP_CAPABLE: u16 = 0x0080;
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CipherSuite {
+    UseGroup,
+    Wep40,
+    Tkip,
+    Ccmp128,
+    Wep104,
+    BipCmac128,
+    GroupNotAllowed,
+    Gcmp128,
+    Gcmp256,
+    Ccmp256,
+    BipGmac128,
+    BipGmac256,
+    BipCmac256,
+    Unknown(u32),
+}
+
+impl CipherSuite {
+    fn from_selector(selector: [u8; 4]) -> CipherSuite {
+        let oui = [selector[0], selector[1], selector[2]];
+        if oui != IEEE_OUI && oui != MICROSOFT_OUI {
+            return CipherSuite::Unknown(u32::from_be_bytes(selector));
+        }
+
+        match selector[3] {
+            0 => CipherSuite::UseGroup,
+            1 => CipherSuite::Wep40,
+            2 => CipherSuite::Tkip,
+            4 => CipherSuite::Ccmp128,
+            5 => CipherSuite::Wep104,
+            6 if oui == IEEE_OUI => CipherSuite::BipCmac128,
+            7 if oui == IEEE_OUI => CipherSuite::GroupNotAllowed,
+            8 if oui == IEEE_OUI => CipherSuite::Gcmp128,
+            9 if oui == IEEE_OUI => CipherSuite::Gcmp256,
+            10 if oui == IEEE_OUI => CipherSuite::Ccmp256,
+            11 if oui == IEEE_OUI => CipherSuite::BipGmac128,
+            12 if oui == IEEE_OUI => CipherSuite::BipGmac256,
+            13 if oui == IEEE_OUI => CipherSuite::BipCmac256,
+            _ => CipherSuite::Unknown(u32::from_be_bytes(selector)),
+        }
+    }
+
+    fn selector(self, oui: [u8; 3]) -> [u8; 4] {
+        let suite_type = match self {
+            CipherSuite::UseGroup => 0,
+            CipherSuite::Wep40 => 1,
+            CipherSuite::Tkip => 2,
+            CipherSuite::Ccmp128 => 4,
+            CipherSuite::Wep104 => 5,
+            CipherSuite::BipCmac128 => 6,
+            CipherSuite::GroupNotAllowed => 7,
+            CipherSuite::Gcmp128 => 8,
+            CipherSuite::Gcmp256 => 9,
+            CipherSuite::Ccmp256 => 10,
+            CipherSuite::BipGmac128 => 11,
+            CipherSuite::BipGmac256 => 12,
+            CipherSuite::BipCmac256 => 13,
+            CipherSuite::Unknown(selector) => return selector.to_be_bytes(),
+        };
+
+        [oui[0], oui[1], oui[2], suite_type]
+    }
+
+    pub fn is_weak(self) -> bool {
+        matches!(
+            self,
+            CipherSuite::Wep40 | CipherSuite::Wep104 | CipherSuite::Tkip
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AkmSuite {
+    Ieee8021X,
+    Psk,
+    FtIeee8021X,
+    FtPsk,
+    Ieee8021XSha256,
+    PskSha256,
+    Tdls,
+    Sae,
+    FtSae,
+    ApPeerKey,
+    Ieee8021XSuiteB,
+    Ieee8021XSuiteB192,
+    FtIeee8021XSha384,
+    FilsSha256,
+    FilsSha384,
+    FtFilsSha256,
+    FtFilsSha384,
+    Owe,
+    FtPskSha384,
+    PskSha384,
+    SaeExt,
+    FtSaeExt,
+    Unknown(u32),
+}
+
+impl AkmSuite {
+    fn from_selector(selector: [u8; 4]) -> AkmSuite {
+        let oui = [selector[0], selector[1], selector[2]];
+        if oui == MICROSOFT_OUI {
+            // WPA only defines 802.1X and PSK
+            return match selector[3] {
+                1 => AkmSuite::Ieee8021X,
+                2 => AkmSuite::Psk,
+                _ => AkmSuite::Unknown(u32::from_be_bytes(selector)),
+            };
+        } else if oui != IEEE_OUI {
+            return AkmSuite::Unknown(u32::from_be_bytes(selector));
+        }
+
+        match selector[3] {
+            1 => AkmSuite::Ieee8021X,
+            2 => AkmSuite::Psk,
+            3 => AkmSuite::FtIeee8021X,
+            4 => AkmSuite::FtPsk,
+            5 => AkmSuite::Ieee8021XSha256,
+            6 => AkmSuite::PskSha256,
+            7 => AkmSuite::Tdls,
+            8 => AkmSuite::Sae,
+            9 => AkmSuite::FtSae,
+            10 => AkmSuite::ApPeerKey,
+            11 => AkmSuite::Ieee8021XSuiteB,
+            12 => AkmSuite::Ieee8021XSuiteB192,
+            13 => AkmSuite::FtIeee8021XSha384,
+            14 => AkmSuite::FilsSha256,
+            15 => AkmSuite::FilsSha384,
+            16 => AkmSuite::FtFilsSha256,
+            17 => AkmSuite::FtFilsSha384,
+            18 => AkmSuite::Owe,
+            19 => AkmSuite::FtPskSha384,
+            20 => AkmSuite::PskSha384,
+            24 => AkmSuite::SaeExt,
+            25 => AkmSuite::FtSaeExt,
+            _ => AkmSuite::Unknown(u32::from_be_bytes(selector)),
+        }
+    }
+
+    fn selector(self, oui: [u8; 3]) -> [u8; 4] {
+        let suite_type = match self {
+            AkmSuite::Ieee8021X => 1,
+            AkmSuite::Psk => 2,
+            AkmSuite::FtIeee8021X => 3,
+            AkmSuite::FtPsk => 4,
+            AkmSuite::Ieee8021XSha256 => 5,
+            AkmSuite::PskSha256 => 6,
+            AkmSuite::Tdls => 7,
+            AkmSuite::Sae => 8,
+            AkmSuite::FtSae => 9,
+            AkmSuite::ApPeerKey => 10,
+            AkmSuite::Ieee8021XSuiteB => 11,
+            AkmSuite::Ieee8021XSuiteB192 => 12,
+            AkmSuite::FtIeee8021XSha384 => 13,
+            AkmSuite::FilsSha256 => 14,
+            AkmSuite::FilsSha384 => 15,
+            AkmSuite::FtFilsSha256 => 16,
+            AkmSuite::FtFilsSha384 => 17,
+            AkmSuite::Owe => 18,
+            AkmSuite::FtPskSha384 => 19,
+            AkmSuite::PskSha384 => 20,
+            AkmSuite::SaeExt => 24,
+            AkmSuite::FtSaeExt => 25,
+            AkmSuite::Unknown(selector) => return selector.to_be_bytes(),
+        };
+
+        [oui[0], oui[1], oui[2], suite_type]
+    }
+
+    pub fn is_psk(self) -> bool {
+        matches!(
+            self,
+            AkmSuite::Psk
+                | AkmSuite::FtPsk
+                | AkmSuite::PskSha256
+                | AkmSuite::FtPskSha384
+                | AkmSuite::PskSha384
+        )
+    }
+
+    pub fn is_sae(self) -> bool {
+        matches!(
+            self,
+            AkmSuite::Sae | AkmSuite::FtSae | AkmSuite::SaeExt | AkmSuite::FtSaeExt
+        )
+    }
+
+    pub fn is_enterprise(self) -> bool {
+        matches!(
+            self,
+            AkmSuite::Ieee8021X
+                | AkmSuite::FtIeee8021X
+                | AkmSuite::Ieee8021XSha256
+                | AkmSuite::Ieee8021XSuiteB
+                | AkmSuite::Ieee8021XSuiteB192
+                | AkmSuite::FtIeee8021XSha384
+                | AkmSuite::FilsSha256
+                | AkmSuite::FilsSha384
+                | AkmSuite::FtFilsSha256
+                | AkmSuite::FtFilsSha384
+        )
+    }
+}
+
+// Content of the RSN element (ID 48) or of the Microsoft WPA vendor element,
+// both share the same layout after the WPA OUI/type.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RsnInfo {
+    pub version: u16,
+    pub group_cipher: CipherSuite,
+    pub pairwise_ciphers: Vec<CipherSuite>,
+    pub akm_suites: Vec<AkmSuite>,
+    pub capabilities: u16,
+    pub pmkids: Vec<[u8; 16]>,
+    pub group_mgmt_cipher: Option<CipherSuite>,
+}
+
+impl Info for RsnInfo {
+    // Trailing fields are optional, missing ones get the defaults from IEEE 802.11-2020 9.4.2.24
+    fn from_bytes(input: &[u8]) -> Result<RsnInfo> {
+        let mut reader = Reader::new(input);
+
+        let version = reader.u16()?;
+        let mut rsn = RsnInfo {
+            version,
+            group_cipher: CipherSuite::Ccmp128,
+            pairwise_ciphers: vec![CipherSuite::Ccmp128],
+            akm_suites: vec![AkmSuite::Ieee8021X],
+            capabilities: 0,
+            pmkids: Vec::new(),
+            group_mgmt_cipher: None,
+        };
+
+        if reader.is_empty() {
+            return Ok(rsn);
+        }
+        rsn.group_cipher = CipherSuite::from_selector(reader.array()?);
+
+        if reader.is_empty() {
+            return Ok(rsn);
+        }
+        let count = reader.u16()?;
+        rsn.pairwise_ciphers = (0..count)
+            .map(|_| reader.array().map(CipherSuite::from_selector))
+            .collect::<Result<Vec<CipherSuite>>>()?;
+
+        if reader.is_empty() {
+            return Ok(rsn);
+        }
+        let count = reader.u16()?;
+        rsn.akm_suites = (0..count)
+            .map(|_| reader.array().map(AkmSuite::from_selector))
+            .collect::<Result<Vec<AkmSuite>>>()?;
+
+        if reader.is_empty() {
+            return Ok(rsn);
+        }
+        rsn.capabilities = reader.u16()?;
+
+        if reader.is_empty() {
+            return Ok(rsn);
+        }
+        let count = reader.u16()?;
+        for _ in 0..count {
+            rsn.pmkids.push(reader.array()?);
+        }
+
+        if reader.is_empty() {
+            return Ok(rsn);
+        }
+        rsn.group_mgmt_cipher = Some(CipherSuite::from_selector(reader.array()?));
+
+        Ok(rsn)
+    }
+}
+
+// Writes the RSN element payload, without the Id and Len
+impl Encode for RsnInfo {
+    fn encode(&self, writer: &mut Writer) {
+        self.encode_with_oui(writer, IEEE_OUI);
+    }
+}
+
+impl RsnInfo {
+    // Vendor specific element payload: OUI 00:50:f2, type 1
+    pub fn from_wpa_vendor_bytes(input: &[u8]) -> Option<RsnInfo> {
+        if input.len() < 4 || input[..3] != MICROSOFT_OUI || input[3] != WPA_OUI_TYPE {
+            return None;
+        }
+
+        RsnInfo::from_bytes(&input[4..]).ok()
+    }
+
+    pub fn to_wpa_vendor_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.bytes(&MICROSOFT_OUI);
+        writer.u8(WPA_OUI_TYPE);
+        self.encode_with_oui(&mut writer, MICROSOFT_OUI);
+        writer.into_bytes()
+    }
+
+    fn encode_with_oui(&self, writer: &mut Writer, oui: [u8; 3]) {
+        writer.u16(self.version);
+        writer.bytes(&self.group_cipher.selector(oui));
+        writer.u16(self.pairwise_ciphers.len() as u16);
+        for cipher in &self.pairwise_ciphers {
+            writer.bytes(&cipher.selector(oui));
+        }
+        writer.u16(self.akm_suites.len() as u16);
+        for akm in &self.akm_suites {
+            writer.bytes(&akm.selector(oui));
+        }
+        writer.u16(self.capabilities);
+
+        if self.pmkids.is_empty() && self.group_mgmt_cipher.is_none() {
+            return;
+        }
+        writer.u16(self.pmkids.len() as u16);
+        for pmkid in &self.pmkids {
+            writer.bytes(pmkid);
+        }
+        if let Some(cipher) = self.group_mgmt_cipher {
+            writer.bytes(&cipher.selector(oui));
+        }
+    }
+
+    pub fn mfp_required(&self) -> bool {
+        self.capabilities & RSN_CAP_MFP_REQUIRED > 0
+    }
+
+    pub fn mfp_capable(&self) -> bool {
+        self.capabilities & RSN_CAP_MFP_CAPABLE > 0
+    }
+
+    fn has_akm<F: Fn(AkmSuite) -> bool>(&self, f: F) -> bool {
+        self.akm_suites.iter().any(|akm| f(*akm))
+    }
+
+    fn has_weak_cipher(&self) -> bool {
+        self.group_cipher.is_weak() || self.pairwise_ciphers.iter().any(|c| c.is_weak())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Security {
+    pub label: String, // Open, WEP, WPA2-PSK, WPA3-SAE, ...
+    pub weak: bool,
+    pub privacy: bool,
+    pub pmf_required: bool,
+    pub rsn: Option<RsnInfo>,
+    pub wpa: Option<RsnInfo>,
+}
+
+impl Security {
+    pub fn new(cap_info: u16, rsn: Option<RsnInfo>, wpa: Option<RsnInfo>) -> Security {
+        let privacy = cap_info & CAP_INFO_PRIVACY > 0;
+        let pmf_required = rsn.as_ref().is_some_and(|rsn| rsn.mfp_required());
+        let label = Security::classify(privacy, &rsn, &wpa);
+
+        let weak = match (&rsn, &wpa) {
+            (_, Some(_)) => true, // WPA1 is deprecated
+            (Some(rsn), None) => rsn.has_weak_cipher(),
+            (None, None) => true, // Open or WEP
+        };
+
+        Security {
+            label,
+            weak,
+            privacy,
+            pmf_required,
+            rsn,
+            wpa,
+        }
+    }
+
+    fn classify(privacy: bool, rsn: &Option<RsnInfo>, wpa: &Option<RsnInfo>) -> String {
+        let rsn_label = rsn.as_ref().map(|rsn| {
+            if rsn.has_akm(|akm| akm == AkmSuite::Owe) {
+                "OWE"
+            } else if rsn.has_akm(AkmSuite::is_sae) && rsn.has_akm(AkmSuite::is_psk) {
+                "WPA2/WPA3-Transition"
+            } else if rsn.has_akm(AkmSuite::is_sae) {
+                "WPA3-SAE"
+            } else if rsn.has_akm(|akm| akm == AkmSuite::Ieee8021XSuiteB192) {
+                "WPA3-Enterprise-192"
+            } else if rsn.has_akm(AkmSuite::is_enterprise) && rsn.mfp_required() {
+                "WPA3-Enterprise"
+            } else if rsn.has_akm(AkmSuite::is_enterprise) {
+                "WPA2-Enterprise"
+            } else if rsn.has_akm(AkmSuite::is_psk) {
+                "WPA2-PSK"
+            } else {
+                "WPA2"
+            }
+        });
+        let wpa_label = wpa.as_ref().map(|wpa| {
+            if wpa.has_akm(AkmSuite::is_enterprise) {
+                "WPA-Enterprise"
+            } else {
+                "WPA-PSK"
+            }
+        });
+
+        match (rsn_label, wpa_label) {
+            (Some(rsn), Some(_)) => format!("WPA/{}", rsn),
+            (Some(rsn), None) => rsn.into(),
+            (None, Some(wpa)) => wpa.into(),
+            (None, None) if privacy => "WEP".into(),
+            (None, None) => "Open".into(),
+        }
+    }
+}
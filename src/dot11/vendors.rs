@@ -1,16 +1,17 @@
 use crate::errors::*;
+use crate::dot11::mac::MacAddr;
 use std::fs::File;
 use std::io::{BufReader, prelude::*};
 use std::collections::HashMap;
 
 #[derive(Clone, Debug)]
 pub struct VendorsDB {
-    db: HashMap<String, String>,
+    db: HashMap<[u8; 3], String>,
 }
 
 impl VendorsDB {
     pub fn from_file(file_name: &str) -> Result<VendorsDB> {
-        let mut vendors: HashMap<String, String> = HashMap::new();
+        let mut vendors: HashMap<[u8; 3], String> = HashMap::new();
         let file = File::open(file_name)?;
         let buf_reader = BufReader::new(file);
 
@@ -20,18 +21,18 @@ impl VendorsDB {
             if v[0].contains("base 16") && v.len() >= 3 {
                 let (vendor_code, _discard) = v[0].split_at(6);
                 let vendor_name = v[2];
-                vendors.insert(vendor_code.to_lowercase(), vendor_name.to_string());
+                if let Ok(code) = u32::from_str_radix(vendor_code, 16) {
+                    let code = code.to_be_bytes();
+                    vendors.insert([code[1], code[2], code[3]], vendor_name.to_string());
+                }
             }
         }
 
         Ok(VendorsDB { db: vendors })
     }
 
-    pub fn lookup(&self, mac: String) -> String {
-        let v: Vec<&str> = mac.split(':').collect();
-        let key: String = format!("{}{}{}", v[0], v[1], v[2]);
-
-        match self.db.get(&key) {
+    pub fn lookup(&self, mac: MacAddr) -> String {
+        match self.db.get(&mac.oui()) {
             Some(key) => key.clone(),
             None => "".into(),
         }
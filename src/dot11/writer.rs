@@ -0,0 +1,86 @@
+use crate::dot11::elements::{EXTENSION_ELEMENT, FRAGMENT_ELEMENT, MAX_ELEMENT_LEN};
+
+// Little endian writer, the counterpart of Reader for building frames
+#[derive(Clone, Debug, Default)]
+pub struct Writer {
+    output: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Writer {
+        Writer::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.output.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.output.is_empty()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.output
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.output.extend_from_slice(bytes);
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.output.push(value);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    // Pads with zeros until the length is a multiple of `align`
+    pub fn align(&mut self, align: usize) {
+        let padding = (align - self.output.len() % align) % align;
+        self.output.resize(self.output.len() + padding, 0);
+    }
+
+    // Tagged parameter, data that doesn't fit the length byte continues in Fragment
+    // elements
+    pub fn element(&mut self, id: u8, data: &[u8]) {
+        self.fragmented(id, &[], data);
+    }
+
+    pub fn extension_element(&mut self, ext_id: u8, data: &[u8]) {
+        self.fragmented(EXTENSION_ELEMENT, &[ext_id], data);
+    }
+
+    fn fragmented(&mut self, id: u8, header: &[u8], data: &[u8]) {
+        let len = data.len().min(MAX_ELEMENT_LEN - header.len());
+        self.u8(id);
+        self.u8((header.len() + len) as u8);
+        self.bytes(header);
+        self.bytes(&data[..len]);
+
+        for fragment in data[len..].chunks(MAX_ELEMENT_LEN) {
+            self.u8(FRAGMENT_ELEMENT);
+            self.u8(fragment.len() as u8);
+            self.bytes(fragment);
+        }
+    }
+}
+
+// Serializes a decoded structure back to its wire format
+pub trait Encode {
+    fn encode(&self, writer: &mut Writer);
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        self.encode(&mut writer);
+        writer.into_bytes()
+    }
+}
@@ -16,4 +16,47 @@ error_chain! {
         ParseIntError(parseIntError);
         CtrlcError(ctrlcError);
     }
+
+    errors {
+        Truncated(needed: usize, available: usize) {
+            description("truncated frame")
+            display("truncated frame: needed {} bytes but only {} available", needed, available)
+        }
+        InvalidProtocolVersion(version: u8) {
+            description("unknown protocol version")
+            display("unknown protocol version: {}", version)
+        }
+        InvalidRadiotap(reason: String) {
+            description("invalid radiotap header")
+            display("invalid radiotap header: {}", reason)
+        }
+        InvalidMacAddress(addr: String) {
+            description("invalid MAC address")
+            display("invalid MAC address: '{}'", addr)
+        }
+        InvalidOui(oui: String) {
+            description("invalid OUI")
+            display("invalid OUI: '{}'", oui)
+        }
+        UnknownSensor(name: String) {
+            description("unknown sensor")
+            display("unknown sensor: '{}'", name)
+        }
+        Calibration(reason: String) {
+            description("distance model calibration failed")
+            display("distance model calibration failed: {}", reason)
+        }
+    }
+}
+
+impl Error {
+    // True when the error comes from a malformed frame rather than from the environment
+    pub fn is_malformed_frame(&self) -> bool {
+        matches!(
+            self.kind(),
+            ErrorKind::Truncated(..)
+                | ErrorKind::InvalidProtocolVersion(..)
+                | ErrorKind::InvalidRadiotap(..)
+        )
+    }
 }
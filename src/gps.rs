@@ -0,0 +1,329 @@
+use crate::errors::*;
+use serde::{Serialize, Deserialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Asks gpsd to stream its reports as JSON
+const WATCH: &[u8] = b"?WATCH={\"enable\":true,\"json\":true};\n";
+// gpsd modes: 0 unknown, 1 no fix, 2 2D, 3 3D
+const MODE_2D: u8 = 2;
+// Receivers report every second, an older fix is no longer where the capture is
+const MAX_FIX_AGE: Duration = Duration::from_secs(10);
+
+// A position from the GPS receiver
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Fix {
+    pub latitude: f64,         // degrees, negative south
+    pub longitude: f64,        // degrees, negative west
+    pub altitude: Option<f64>, // meters above the mean sea level
+    pub time: Option<String>,  // UTC, ISO 8601
+}
+
+// Where an access point was heard, and how loud
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Sighting {
+    pub fix: Fix,
+    pub signal: i8, // dBm, 0 when the capture has no signal
+}
+
+// The GPS tags of an access point. The strongest sighting is the estimate of its
+// position: the signal peaks closest to the transmitter.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Sightings {
+    pub count: u64,
+    pub last: Sighting,
+    pub strongest: Sighting,
+}
+
+impl Sightings {
+    pub fn new(fix: Fix, signal: i8) -> Sightings {
+        let sighting = Sighting { fix, signal };
+        Sightings {
+            count: 1,
+            last: sighting.clone(),
+            strongest: sighting,
+        }
+    }
+
+    pub fn record(&mut self, fix: Fix, signal: i8) {
+        self.count += 1;
+        self.last = Sighting { fix, signal };
+        if signal != 0 && (self.strongest.signal == 0 || signal > self.strongest.signal) {
+            self.strongest = self.last.clone();
+        }
+    }
+}
+
+// Time-Position-Velocity report, https://gpsd.gitlab.io/gpsd/gpsd_json.html
+#[derive(Deserialize, Debug)]
+struct Report {
+    class: String,
+    #[serde(default)]
+    mode: u8,
+    time: Option<String>,
+    lat: Option<f64>,
+    lon: Option<f64>,
+    alt: Option<f64>,
+    #[serde(rename = "altMSL")]
+    alt_msl: Option<f64>,
+}
+
+impl Report {
+    // None when the receiver has no fix
+    fn fix(self) -> Option<Fix> {
+        if self.mode < MODE_2D {
+            return None;
+        }
+        Some(Fix {
+            latitude: self.lat?,
+            longitude: self.lon?,
+            altitude: self.alt_msl.or(self.alt),
+            time: self.time,
+        })
+    }
+}
+
+// The latest fix reported by gpsd and when it arrived, updated from a background
+// thread
+#[derive(Clone, Debug, Default)]
+pub struct Gpsd {
+    fix: Arc<Mutex<Option<(Fix, Instant)>>>,
+}
+
+impl Gpsd {
+    // addr is host:port, gpsd listens on localhost:2947
+    pub fn connect(addr: &str) -> Result<Gpsd> {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.write_all(WATCH)?;
+
+        let gpsd = Gpsd::default();
+        let reader = BufReader::new(stream);
+        let shared = gpsd.clone();
+        thread::spawn(move || shared.follow(reader));
+        Ok(gpsd)
+    }
+
+    // Reads the reports until the connection closes. The fix is cleared when the
+    // receiver loses it, and once the connection is gone.
+    pub fn follow<R: BufRead>(&self, reader: R) {
+        for line in reader.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            // VERSION, DEVICES, SKY... reports are skipped
+            match serde_json::from_str::<Report>(&line) {
+                Ok(report) if report.class == "TPV" => self.set(report.fix()),
+                _ => continue,
+            }
+        }
+        self.set(None);
+    }
+
+    fn set(&self, fix: Option<Fix>) {
+        if let Ok(mut current) = self.fix.lock() {
+            *current = fix.map(|fix| (fix, Instant::now()));
+        }
+    }
+
+    // None when gpsd went quiet, e.g. the receiver was unplugged
+    pub fn fix(&self) -> Option<Fix> {
+        match self.fix.lock() {
+            Ok(current) => match *current {
+                Some((ref fix, received)) if received.elapsed() <= MAX_FIX_AGE => Some(fix.clone()),
+                _ => None,
+            },
+            Err(_) => None,
+        }
+    }
+}
+
+// Fixes of a NMEA 0183 log, replayed by the capture time of the frames
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NmeaTrack {
+    pub fixes: Vec<(Duration, Option<Fix>)>, // since the Unix epoch, sorted. None: no fix
+}
+
+impl NmeaTrack {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<NmeaTrack> {
+        NmeaTrack::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    // RMC sentences give the date, GGA ones the altitude. Sentences with the same
+    // time are merged into one fix. RMC void and GGA invalid sentences are kept as
+    // gaps of the track.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<NmeaTrack> {
+        let mut track = NmeaTrack::default();
+        let mut date = None;
+        let mut altitude = None;
+
+        for line in reader.lines() {
+            let line = line?;
+            let fields = match nmea_fields(line.trim()) {
+                Some(fields) => fields,
+                None => continue,
+            };
+            let position = match fields[0].get(2..) {
+                Some("RMC") if fields.len() > 9 => {
+                    date = parse_date(fields[9]).or(date);
+                    let position = parse_position(&fields[3..7]).filter(|_| fields[2] == "A");
+                    if position.is_none() {
+                        altitude = None;
+                    }
+                    Some(position)
+                }
+                Some("GGA") if fields.len() > 9 => {
+                    let position = parse_position(&fields[2..6]).filter(|_| fields[6] != "0");
+                    altitude = fields[9].parse::<f64>().ok().filter(|_| position.is_some());
+                    Some(position)
+                }
+                _ => None,
+            };
+            let (position, days, secs) = match (position, date, parse_time(fields[1])) {
+                (Some(position), Some(days), Some(secs)) => (position, days, secs),
+                _ => continue,
+            };
+
+            let timestamp = Duration::from_secs_f64(days as f64 * 86_400.0 + secs);
+            let fix = position.map(|(latitude, longitude)| Fix {
+                latitude,
+                longitude,
+                altitude,
+                time: Some(iso_time(days, secs)),
+            });
+            match track.fixes.last_mut() {
+                Some(last) if last.0 == timestamp => last.1 = fix,
+                _ => track.fixes.push((timestamp, fix)),
+            }
+        }
+        track.fixes.sort_by_key(|(time, _)| *time);
+        Ok(track)
+    }
+
+    // The last fix at or before the timestamp, None during a gap or when the fix
+    // is too old, e.g. past the end of the log
+    pub fn fix_at(&self, timestamp: Duration) -> Option<Fix> {
+        let index = self.fixes.partition_point(|(time, _)| *time <= timestamp);
+        let (time, fix) = &self.fixes[index.checked_sub(1)?];
+        if timestamp - *time > MAX_FIX_AGE {
+            return None;
+        }
+        fix.clone()
+    }
+}
+
+// Where the fixes come from
+#[derive(Clone, Debug)]
+pub enum Gps {
+    Gpsd(Gpsd),
+    Nmea(NmeaTrack),
+}
+
+impl Gps {
+    // Position of a frame captured at timestamp
+    pub fn fix_at(&self, timestamp: Duration) -> Option<Fix> {
+        match self {
+            Gps::Gpsd(gpsd) => gpsd.fix(),
+            Gps::Nmea(track) => track.fix_at(timestamp),
+        }
+    }
+}
+
+// Fields of a sentence with a valid checksum, the first one is the talker and type
+fn nmea_fields(sentence: &str) -> Option<Vec<&str>> {
+    let body = sentence.strip_prefix('$')?;
+    let body = match body.split_once('*') {
+        Some((body, checksum)) => {
+            let expected = u8::from_str_radix(checksum, 16).ok()?;
+            if body.bytes().fold(0, |sum, byte| sum ^ byte) != expected {
+                return None;
+            }
+            body
+        }
+        None => body,
+    };
+    let fields: Vec<&str> = body.split(',').collect();
+    if fields[0].len() < 5 || fields.len() < 2 {
+        return None;
+    }
+    Some(fields)
+}
+
+// ddmm.mmmm,N,dddmm.mmmm,E
+fn parse_position(fields: &[&str]) -> Option<(f64, f64)> {
+    let coordinate = |value: &str, hemisphere: &str, negative: &str| -> Option<f64> {
+        let value: f64 = value.parse().ok()?;
+        let degrees = (value / 100.0).trunc();
+        let coordinate = degrees + (value - degrees * 100.0) / 60.0;
+        Some(if hemisphere == negative {
+            -coordinate
+        } else {
+            coordinate
+        })
+    };
+
+    Some((
+        coordinate(fields[0], fields[1], "S")?,
+        coordinate(fields[2], fields[3], "W")?,
+    ))
+}
+
+// hhmmss.ss to seconds of the day
+fn parse_time(field: &str) -> Option<f64> {
+    let hours: u32 = field.get(0..2)?.parse().ok()?;
+    let minutes: u32 = field.get(2..4)?.parse().ok()?;
+    let seconds: f64 = field.get(4..)?.parse().ok()?;
+    Some(f64::from(hours * 3600 + minutes * 60) + seconds)
+}
+
+// ddmmyy to days since the Unix epoch
+fn parse_date(field: &str) -> Option<i64> {
+    let day: i64 = field.get(0..2)?.parse().ok()?;
+    let month: i64 = field.get(2..4)?.parse().ok()?;
+    let year: i64 = 2000 + field.get(4..6)?.parse::<i64>().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+fn iso_time(days: i64, secs: f64) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    let secs = secs as u32;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
@@ -0,0 +1,254 @@
+use crate::errors::*;
+use crate::dot11::eapol::*;
+use crate::dot11::header::*;
+use crate::dot11::info::*;
+use crate::dot11::mac::MacAddr;
+use crate::pcap_writer::{PcapWriter, Rotation};
+use crate::source::DLT_IEEE802_11_RADIO;
+use pcap::Linktype;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+use std::time::Duration;
+
+// hashcat 22000 message pairs, the EAPOL packet is always taken from M2
+const MESSAGE_PAIR_M1_M2: u8 = 0x00;
+const MESSAGE_PAIR_M2_M3: u8 = 0x02;
+
+// 4-way handshake messages seen between a client and its access point
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct HandshakeState {
+    pub m1: bool,
+    pub m2: bool,
+    pub m3: bool,
+    pub m4: bool,
+    pub pmkid: bool,
+}
+
+impl HandshakeState {
+    pub fn record(&mut self, key: &EapolKey) {
+        match key.message() {
+            Some(HandshakeMessage::M1) => self.m1 = true,
+            Some(HandshakeMessage::M2) => self.m2 = true,
+            Some(HandshakeMessage::M3) => self.m3 = true,
+            Some(HandshakeMessage::M4) => self.m4 = true,
+            None => {}
+        }
+        if key.pmkid().is_some() {
+            self.pmkid = true;
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.m1 && self.m2 && self.m3 && self.m4
+    }
+}
+
+// Radiotap frame as it was captured, the timestamp is relative to the Unix epoch
+#[derive(Clone, Debug, PartialEq)]
+pub struct CapturedFrame {
+    pub timestamp: Duration,
+    pub data: Vec<u8>,
+}
+
+// The last copy of each message exchanged between a client and an access point
+#[derive(Clone, Debug, Default)]
+pub struct Handshake {
+    pub bssid: MacAddr,
+    pub client: MacAddr,
+    pub state: HandshakeState,
+    pub pmkid: Option<[u8; 16]>,
+    pub messages: [Option<EapolKey>; 4],
+    pub frames: [Option<CapturedFrame>; 4],
+}
+
+impl Handshake {
+    fn new(bssid: MacAddr, client: MacAddr) -> Handshake {
+        Handshake {
+            bssid,
+            client,
+            ..Default::default()
+        }
+    }
+
+    pub fn message(&self, message: HandshakeMessage) -> Option<&EapolKey> {
+        self.messages[message as usize].as_ref()
+    }
+
+    // M2 and the ANonce it answers: from the M1 with the same replay counter,
+    // or else from the M3 that followed it
+    pub fn authentication(&self) -> Option<(&EapolKey, [u8; 32], u8)> {
+        let m2 = self.message(HandshakeMessage::M2)?;
+
+        if let Some(m1) = self.message(HandshakeMessage::M1) {
+            if m1.replay_counter == m2.replay_counter {
+                return Some((m2, m1.nonce, MESSAGE_PAIR_M1_M2));
+            }
+        }
+        match self.message(HandshakeMessage::M3) {
+            Some(m3) if m3.replay_counter == m2.replay_counter.wrapping_add(1) => {
+                Some((m2, m3.nonce, MESSAGE_PAIR_M2_M3))
+            }
+            _ => None,
+        }
+    }
+
+    // Enough material to recover the passphrase offline, every complete handshake is
+    pub fn is_crackable(&self) -> bool {
+        self.pmkid.is_some() || self.authentication().is_some()
+    }
+
+    // hashcat -m 22000 lines, https://hashcat.net/wiki/doku.php?id=cracking_wpawpa2
+    pub fn hashcat_lines(&self, ssid: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        let ap = hex(&self.bssid.octets());
+        let client = hex(&self.client.octets());
+        let essid = hex(ssid.as_bytes());
+
+        if let Some(pmkid) = self.pmkid {
+            lines.push(format!(
+                "WPA*01*{}*{}*{}*{}***",
+                hex(&pmkid),
+                ap,
+                client,
+                essid
+            ));
+        }
+        if let Some((m2, anonce, message_pair)) = self.authentication() {
+            lines.push(format!(
+                "WPA*02*{}*{}*{}*{}*{}*{}*{:02x}",
+                hex(&m2.mic),
+                ap,
+                client,
+                essid,
+                hex(&anonce),
+                hex(&m2.eapol_with_zero_mic()),
+                message_pair
+            ));
+        }
+        lines
+    }
+
+    fn record(&mut self, key: EapolKey, message: HandshakeMessage, frame: CapturedFrame) {
+        self.state.record(&key);
+        if let Some(pmkid) = key.pmkid() {
+            self.pmkid = Some(pmkid);
+        }
+        self.messages[message as usize] = Some(key);
+        self.frames[message as usize] = Some(frame);
+    }
+}
+
+// Collects the EAPOL-Key frames of every client, plus one beacon per access point so
+// the exported captures carry the SSID
+#[derive(Clone, Debug, Default)]
+pub struct HandshakeTracker {
+    pub handshakes: HashMap<(MacAddr, MacAddr), Handshake>, // (BSSID, client)
+    pub ssids: HashMap<MacAddr, String>,
+    beacons: HashMap<MacAddr, CapturedFrame>,
+}
+
+impl HandshakeTracker {
+    pub fn new() -> HandshakeTracker {
+        HandshakeTracker::default()
+    }
+
+    // frame is the radiotap frame the header was parsed from
+    pub fn capture(&mut self, frame: &[u8], header: &Dot11Header, timestamp: Duration) {
+        let captured = || CapturedFrame {
+            timestamp,
+            data: frame.to_vec(),
+        };
+
+        match header.info {
+            // The first beacon of each access point is enough
+            BodyInformation::Beacon(ref beacon)
+                if !beacon.ssid.value.is_empty() && !self.beacons.contains_key(&header.bssid) =>
+            {
+                self.ssids.insert(header.bssid, beacon.ssid.value.clone());
+                self.beacons.insert(header.bssid, captured());
+            }
+            BodyInformation::ProbeResponse(ref probe) if !probe.ssid.value.is_empty() => {
+                self.ssids
+                    .entry(header.bssid)
+                    .or_insert_with(|| probe.ssid.value.clone());
+            }
+            BodyInformation::EapolKey(ref key) => {
+                if let Some(message) = key.message() {
+                    // M1 and M3 are sent by the access point
+                    let client = match message {
+                        HandshakeMessage::M1 | HandshakeMessage::M3 => header.receiver,
+                        HandshakeMessage::M2 | HandshakeMessage::M4 => header.transmitter,
+                    };
+                    self.handshakes
+                        .entry((header.bssid, client))
+                        .or_insert_with(|| Handshake::new(header.bssid, client))
+                        .record(key.clone(), message, captured());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn handshake(&self, bssid: MacAddr, client: MacAddr) -> Option<&Handshake> {
+        self.handshakes.get(&(bssid, client))
+    }
+
+    // Crackable handshakes of the access points whose SSID is known
+    pub fn crackable(&self) -> Vec<(&Handshake, &str)> {
+        let mut handshakes: Vec<_> = self
+            .handshakes
+            .values()
+            .filter(|handshake| handshake.is_crackable())
+            .filter_map(|handshake| {
+                let ssid = self.ssids.get(&handshake.bssid)?;
+                Some((handshake, ssid.as_str()))
+            })
+            .collect();
+        handshakes.sort_by_key(|(handshake, _)| (handshake.bssid, handshake.client));
+        handshakes
+    }
+
+    // Writes a hashcat 22000 file, returns the number of hashes written
+    pub fn write_hashcat<P: AsRef<Path>>(&self, path: P) -> Result<usize> {
+        let lines: Vec<String> = self
+            .crackable()
+            .iter()
+            .flat_map(|(handshake, ssid)| handshake.hashcat_lines(ssid))
+            .collect();
+
+        let mut file = File::create(path)?;
+        for line in &lines {
+            writeln!(file, "{}", line)?;
+        }
+        Ok(lines.len())
+    }
+
+    // Writes the beacons and EAPOL frames of the crackable handshakes to a pcap file,
+    // returns the number of frames written
+    pub fn write_pcap<P: AsRef<Path>>(&self, path: P) -> Result<usize> {
+        let mut frames: Vec<&CapturedFrame> = Vec::new();
+        for (handshake, _) in self.crackable() {
+            if let Some(beacon) = self.beacons.get(&handshake.bssid) {
+                if !frames.contains(&beacon) {
+                    frames.push(beacon);
+                }
+            }
+            frames.extend(handshake.frames.iter().flatten());
+        }
+        frames.sort_by_key(|frame| frame.timestamp);
+
+        let linktype = Linktype(DLT_IEEE802_11_RADIO);
+        let mut writer = PcapWriter::new(path, linktype, Rotation::default())?;
+        for frame in &frames {
+            writer.write_frame(&frame.data, frame.timestamp)?;
+        }
+        Ok(frames.len())
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
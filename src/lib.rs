@@ -0,0 +1,24 @@
+//! Scans nearby wifi networks and the devices connected to each network.
+//!
+//! The `dot11` module parses radiotap/802.11 frames, the `mapper` turns them
+//! into access points, clients and people, and `util` exports the results as
+//! netjson. Frames are fed through a `scanner::Scanner` from any
+//! `source::PacketSource`, and `wids` raises alerts on suspicious activity.
+
+#[macro_use]
+extern crate error_chain;
+
+pub mod distance;
+pub mod dot11;
+pub mod errors;
+pub mod gps;
+pub mod handshake;
+pub mod mapper;
+pub mod pcap_writer;
+pub mod positioning;
+pub mod radiotap_writer;
+pub mod scanner;
+pub mod signal;
+pub mod source;
+pub mod util;
+pub mod wids;
@@ -1,4 +1,4 @@
-use crate::errors::*;
+use nearby::errors::*;
 use std::thread;
 use std::sync::Arc;
 use std::time::Duration;
@@ -1,29 +1,31 @@
-use pcap;
-use radiotap;
-
 #[macro_use]
 extern crate error_chain;
 
-mod dot11;
-mod errors;
 mod linux_device_management;
-mod mapper;
 mod server;
-mod util;
 
-use crate::errors::*;
-use crate::dot11::header::*;
-use crate::mapper::*;
-use bytes::{Buf};
-use std::io::Cursor;
+use nearby::distance::*;
+use nearby::errors::*;
+use nearby::gps::*;
+use nearby::mapper::*;
+use nearby::pcap_writer::*;
+use nearby::positioning::*;
+use nearby::scanner::*;
+use nearby::source::*;
+use nearby::util;
+use nearby::wids::*;
+use nearby::wids::evil_twin::*;
 use clap::{Arg, App};
-use std::time::Instant;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::thread;
+use std::time::Duration;
 use console::{style, Emoji, Term};
 use crate::linux_device_management::NetworkInterface;
 
-const TIMEOUT: i32 = 10;
 const DEFAULT_EXECUTION_WINDOW: usize = 10;
 const LONG_EXECUTION_WINDOW: usize = 15;
+const SERVED_ALERTS: usize = 1000;
 
 fn main() -> Result<()> {
     let matches = App::new("Nearby")
@@ -35,6 +37,82 @@ fn main() -> Result<()> {
                 .multiple(true)
                 .help("wireless interface")
                 .required(false),
+            Arg::with_name("read")
+                .takes_value(true)
+                .short("r")
+                .long("read")
+                .help("Read frames from a pcap/pcapng capture file, use - for stdin")
+                .required(false),
+            Arg::with_name("write")
+                .takes_value(true)
+                .short("w")
+                .long("write")
+                .help("Save the captured frames to a pcap file")
+                .required(false),
+            Arg::with_name("rotate-size")
+                .takes_value(true)
+                .long("rotate-size")
+                .requires("write")
+                .help("Start a new pcap file after the given number of megabytes")
+                .required(false),
+            Arg::with_name("rotate-time")
+                .takes_value(true)
+                .long("rotate-time")
+                .requires("write")
+                .help("Start a new pcap file after the given number of seconds")
+                .required(false),
+            Arg::with_name("handshakes")
+                .takes_value(true)
+                .long("handshakes")
+                .help("Save the captured WPA handshakes to <prefix>.22000 (hashcat) and <prefix>.pcap")
+                .required(false),
+            Arg::with_name("alerts")
+                .takes_value(true)
+                .long("alerts")
+                .help("Append the intrusion detection alerts to a JSON lines file")
+                .required(false),
+            Arg::with_name("baseline")
+                .takes_value(true)
+                .long("baseline")
+                .help("JSON file with the authorized networks, to detect rogue access points")
+                .required(false),
+            Arg::with_name("expire")
+                .takes_value(true)
+                .long("expire")
+                .help("Forget the devices not seen for the given number of seconds")
+                .required(false),
+            Arg::with_name("distance-model")
+                .takes_value(true)
+                .long("distance-model")
+                .help("JSON file with the model used to estimate the distance of people")
+                .required(false),
+            Arg::with_name("gpsd")
+                .takes_value(true)
+                .long("gpsd")
+                .conflicts_with("nmea")
+                .help("Tag the access points with the position reported by gpsd, at host:port")
+                .required(false),
+            Arg::with_name("nmea")
+                .takes_value(true)
+                .long("nmea")
+                .help("Tag the access points with the positions of a NMEA log, replayed with the capture")
+                .required(false),
+            Arg::with_name("calibrate")
+                .takes_value(true)
+                .long("calibrate")
+                .help("Fit a log-distance model to a JSON file of signals at known distances")
+                .required(false),
+            Arg::with_name("trilaterate")
+                .takes_value(true)
+                .long("trilaterate")
+                .help("Locate the devices heard by the sensors of a deployment JSON file, prints GeoJSON")
+                .required(false),
+            Arg::with_name("observations")
+                .takes_value(true)
+                .long("observations")
+                .requires("trilaterate")
+                .help("JSON lines file of sensor observations to trilaterate, use - for stdin")
+                .required(false),
             Arg::with_name("graph")
                 .help("Visualize the netjson")
                 .short("g")
@@ -53,92 +131,167 @@ fn main() -> Result<()> {
         ])
         .get_matches();
 
-    if let Some(device) = matches.value_of("interface") {
+    if let Some(file) = matches.value_of("calibrate") {
+        let samples = CalibrationSample::from_file(file)?;
+        let model = DistanceModel::LogDistance(LogDistance::fit(&samples)?);
+        println!("{}", serde_json::to_string_pretty(&model)?);
+        return Ok(());
+    }
+
+    if let Some(file) = matches.value_of("trilaterate") {
+        let mut trilateration = Trilateration::new(Deployment::from_file(file)?);
+        trilateration.load_outputs()?;
+        let observations = match matches.value_of("observations") {
+            Some("-") => Observation::from_reader(io::stdin().lock())?,
+            Some(file) => Observation::from_reader(BufReader::new(File::open(file)?))?,
+            None => Vec::new(),
+        };
+        for observation in &observations {
+            trilateration.observe(observation)?;
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&trilateration.geojson())?
+        );
+        return Ok(());
+    }
+
+    let people = matches.is_present("people");
+    let netjson = matches.is_present("netjson");
+    let handshakes = matches.value_of("handshakes");
+    let distance = match matches.value_of("distance-model") {
+        Some(file) => DistanceModel::from_file(file)?,
+        None => DistanceModel::default(),
+    };
+    let expiry = match matches.value_of("expire") {
+        Some(secs) => Some(Duration::from_secs(secs.parse::<u64>()?)),
+        None => None,
+    };
+
+    let gps = match (matches.value_of("gpsd"), matches.value_of("nmea")) {
+        (Some(addr), _) => Some(Gps::Gpsd(Gpsd::connect(addr)?)),
+        (None, Some(file)) => Some(Gps::Nmea(NmeaTrack::from_file(file)?)),
+        (None, None) => None,
+    };
+
+    let baseline = match matches.value_of("baseline") {
+        Some(file) => Baseline::from_file(file)?,
+        None => Baseline::default(),
+    };
+
+    // Alerts are printed on the console and delivered to every sink
+    let mut sinks: Vec<Box<dyn AlertSink>> = Vec::new();
+    if let Some(file) = matches.value_of("alerts") {
+        sinks.push(Box::new(JsonLinesSink::new(file)?));
+    }
+
+    // The web server runs during the scan, so the alerts can be followed live
+    let server = if matches.is_present("graph") {
+        let alerts = SharedAlerts::new(SERVED_ALERTS);
+        sinks.push(Box::new(alerts.clone()));
+        Some(thread::spawn(move || server::start(alerts)))
+    } else {
+        None
+    };
+
+    if let Some(file) = matches.value_of("read") {
+        let mut source = if file == "-" {
+            PcapSource::from_stdin()?
+        } else {
+            PcapSource::from_file(file)?
+        };
+        let mut mapper = Mapper::new()?;
+        mapper.expiry = expiry;
+        mapper.distance = distance;
+        mapper.gps = gps;
+        let mut scanner = Scanner::new(mapper, people, None);
+        scanner.wids.evil_twin = EvilTwinDetector::new(baseline);
+        let term = Term::stdout();
+
+        loop {
+            let event = scanner.step(&mut source)?;
+            report_alerts(&term, &mut scanner, &mut sinks)?;
+            report_departures(&term, &mut scanner)?;
+            match event {
+                ScanEvent::AccessPoint(ap) => print_access_point(&term, *ap)?,
+                ScanEvent::Finished => break,
+                _ => continue,
+            }
+        }
+
+        print_malformed(&scanner);
+        if let Some(prefix) = handshakes {
+            save_handshakes(&scanner, prefix)?;
+        }
+        print_results(scanner.mapper, people, netjson)?;
+    } else if let Some(device) = matches.value_of("interface") {
         let mut wifi = NetworkInterface::new(device)?;
 
         if let Ok(_value) = wifi.monitor_mode_on() {
             wifi.find_monitor_interfaces()?;
             wifi.find_supported_channels()?;
 
-            let capture = pcap::Capture::from_device(&device[..])?;
-            let mut cap = match capture.timeout(TIMEOUT).rfmon(true).open() {
-                Ok(cap) => cap,
-                Err(_e) => bail!("Can not open device, you need root access"),
-            };
+            let mut source = PcapSource::live(device)?;
+
+            if let Some(file) = matches.value_of("write") {
+                let mut rotation = Rotation::default();
+                if let Some(size) = matches.value_of("rotate-size") {
+                    rotation.max_size = Some(size.parse::<u64>()? * 1024 * 1024);
+                }
+                if let Some(secs) = matches.value_of("rotate-time") {
+                    rotation.max_time = Some(Duration::from_secs(secs.parse::<u64>()?));
+                }
+                let linktype = pcap::Linktype(DLT_IEEE802_11_RADIO);
+                source.set_writer(PcapWriter::new(file, linktype, rotation)?);
+            }
 
-            let people = matches.is_present("people");
             let mut execution_window = DEFAULT_EXECUTION_WINDOW * wifi.channels.len();
 
             if people {
                 execution_window = LONG_EXECUTION_WINDOW * wifi.channels.len();
             }
-            // DLT_IEEE802_11_RADIO = 127
-            if let Ok(_result) = cap.set_datalink(pcap::Linktype(127)) {
-                let mut mapper = Mapper::new()?;
-                let term = Term::stdout();
-                let start = Instant::now();
-
-                wifi.start_channel_switch();
-                while start.elapsed().as_secs() < (execution_window as u64) {
-                    let remaining = (execution_window as u64) - start.elapsed().as_secs();
-                    term.write_line(&format!(
-                        "{} Searching devices: remaining {} sec. Use CTRL-C to stop ",
-                        Emoji("📶", "📡 "),
-                        style(remaining).red()
-                    ))?;
-                    term.move_cursor_up(1)?;
-                    match cap.next() {
-                        Ok(packet) => {
-                            let data: &[u8] = &packet;
-                            let radiotap_header = radiotap::Radiotap::from_bytes(&packet);
-                            if radiotap_header.is_ok() {
-                                if let Ok(tap_data) = radiotap_header {
-                                    let mut buf = Cursor::new(data);
-                                    buf.advance(tap_data.header.length);
-
-                                    let dot11_header = Dot11Header::from_bytes(&buf.bytes())?;
-                                    if let Some(ap) = mapper.map(tap_data, dot11_header, people) {
-                                        term.write_line(&format!(
-                                            "Access point {} signal {} current channel {} {}",
-                                            style(ap.ssid).cyan(),
-                                            style(ap.signal).cyan(),
-                                            style(ap.current_channel).cyan(),
-                                            "                      "
-                                        ))?;
-                                    }
-                                }
-                            }
-                        }
-                        // There were no packets on the interface before the timeout
-                        Err(pcap::Error::TimeoutExpired) => {
-                            continue;
-                        }
-                        Err(e) => bail!("Unexpect error: {}", e.to_string()),
-                    }
-
-                    if !wifi.running() {
-                        println!();
-                        println!("=>>>>>> Process stopped by user (CTRL-C)");
-                        break;
-                    }
+
+            let window = Duration::from_secs(execution_window as u64);
+            let mut mapper = Mapper::new()?;
+            mapper.expiry = expiry;
+            mapper.distance = distance;
+            mapper.gps = gps;
+            let mut scanner = Scanner::new(mapper, people, Some(window));
+            scanner.wids.evil_twin = EvilTwinDetector::new(baseline);
+            let term = Term::stdout();
+
+            wifi.start_channel_switch();
+            loop {
+                let remaining = scanner.remaining().unwrap_or_default().as_secs();
+                term.write_line(&format!(
+                    "{} Searching devices: remaining {} sec. Use CTRL-C to stop ",
+                    Emoji("📶", "📡 "),
+                    style(remaining).red()
+                ))?;
+                term.move_cursor_up(1)?;
+                let event = scanner.step(&mut source)?;
+                report_alerts(&term, &mut scanner, &mut sinks)?;
+                report_departures(&term, &mut scanner)?;
+                match event {
+                    ScanEvent::AccessPoint(ap) => print_access_point(&term, *ap)?,
+                    ScanEvent::Finished => break,
+                    _ => {}
                 }
 
-                term.clear_line()?;
-
-                if people {
-                    println!("{}", util::format_people_json(mapper)?);
-                } else {
-                    let netjson = util::create_netjson(mapper)?;
-                    if matches.is_present("netjson") {
-                        let output = matches.value_of("netjson").unwrap_or("networks.json");
-                        util::save_netjson(output, netjson)?;
-                    } else {
-                        println!("{}", netjson);
-                    }
+                if !wifi.running() {
+                    println!();
+                    println!("=>>>>>> Process stopped by user (CTRL-C)");
+                    break;
                 }
-            } else {
-                bail!("Can not set datalink")
             }
+
+            term.clear_line()?;
+
+            print_malformed(&scanner);
+            if let Some(prefix) = handshakes {
+                save_handshakes(&scanner, prefix)?;
+            }
+            print_results(scanner.mapper, people, netjson)?;
         }
 
         if let Err(e) = wifi.monitor_mode_off() {
@@ -146,8 +299,98 @@ fn main() -> Result<()> {
         }
     }
 
-    if matches.is_present("graph") {
-        server::start();
+    if let Some(server) = server {
+        let _ = server.join();
+    }
+
+    Ok(())
+}
+
+fn print_access_point(term: &Term, ap: Collection) -> Result<()> {
+    let security = if ap.security.weak {
+        style(ap.security.label).red()
+    } else {
+        style(ap.security.label).cyan()
+    };
+    term.write_line(&format!(
+        "Access point {} signal {} current channel {} security {} {}",
+        style(ap.ssid).cyan(),
+        style(ap.signal).cyan(),
+        style(ap.current_channel).cyan(),
+        security,
+        "                      "
+    ))?;
+
+    Ok(())
+}
+
+fn report_alerts(
+    term: &Term, scanner: &mut Scanner, sinks: &mut [Box<dyn AlertSink>],
+) -> Result<()> {
+    for alert in scanner.take_alerts() {
+        term.write_line(&format!(
+            "{} {} {}",
+            Emoji("🚨", "!!"),
+            style(&alert).red(),
+            "                      "
+        ))?;
+        for sink in sinks.iter_mut() {
+            sink.emit(&alert)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn report_departures(term: &Term, scanner: &mut Scanner) -> Result<()> {
+    for departure in scanner.take_departures() {
+        let device = match departure.kind {
+            DeviceKind::AccessPoint => "Access point",
+            DeviceKind::Client => "Client",
+            DeviceKind::Person => "Person",
+        };
+        term.write_line(&format!(
+            "{} {} left, seen for {} sec, {} frames {}",
+            device,
+            style(departure.mac).yellow(),
+            departure.presence.last_seen - departure.presence.first_seen,
+            departure.presence.frames,
+            "                      "
+        ))?;
+    }
+
+    Ok(())
+}
+
+fn print_malformed(scanner: &Scanner) {
+    if scanner.malformed_frames > 0 {
+        eprintln!("{} malformed frames skipped", scanner.malformed_frames);
+    }
+}
+
+fn save_handshakes(scanner: &Scanner, prefix: &str) -> Result<()> {
+    let hashes = scanner
+        .handshakes
+        .write_hashcat(format!("{}.22000", prefix))?;
+    let frames = scanner.handshakes.write_pcap(format!("{}.pcap", prefix))?;
+    eprintln!(
+        "{} hashes saved to {}.22000, {} frames to {}.pcap",
+        hashes, prefix, frames, prefix
+    );
+
+    Ok(())
+}
+
+fn print_results(mapper: Mapper, people: bool, netjson: bool) -> Result<()> {
+    if people {
+        println!("{}", util::format_people_json(mapper)?);
+    } else {
+        let content = util::create_netjson(mapper)?;
+        if netjson {
+            util::save_netjson("networks.json", content)?;
+        } else {
+            println!("{}", content);
+        }
     }
 
     Ok(())
@@ -1,16 +1,28 @@
 use crate::errors::*;
+use crate::distance::DistanceModel;
 use crate::dot11::vendors::*;
+use crate::dot11::auth::*;
+use crate::dot11::eapol::EapolKey;
 use crate::dot11::header::*;
 use crate::dot11::info::*;
+use crate::dot11::mac::MacAddr;
+use crate::dot11::phy::*;
+use crate::dot11::qos::AccessCategory;
+use crate::dot11::security::*;
+use crate::gps::*;
+use crate::handshake::HandshakeState;
+use crate::signal::*;
+use crate::util::unix_time;
 use radiotap::Radiotap;
 use std::collections::HashMap;
+use std::time::Duration;
 use serde::{Serialize, Deserialize};
 
-const FREE_SPACE_PATH_LOSS: f32 = 27.55;
+// Connection events kept per client, the oldest are dropped first
+const MAX_CONNECTION_EVENTS: usize = 32;
+// How often the departed devices are looked for
+const EXPIRY_SWEEP: Duration = Duration::from_secs(1);
 static PROTOCOL: &str = "802.11";
-static BROADCAST: &str = "ff:ff:ff:ff:ff:ff";
-static UNSPECIFIED: &str = "00:00:00:00:00:00";
-static MULTICAST: &str = "33:33:00:";
 
 // Access Point Information mapped to NetJson format
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -27,12 +39,18 @@ pub struct Collection {
     pub ssid: String,
     pub protocol: String,
     pub version: String,
-    pub router_id: String, // BSSID
+    pub router_id: MacAddr, // BSSID
     pub label: String,
-    pub signal: i8,
+    pub signal: i8, // smoothed, see rssi
+    pub quality: SignalQuality,
+    pub rssi: SignalStats,
     pub current_channel: u8,
-    nodes: Vec<Node>,
-    links: Vec<Link>,
+    pub security: Security,
+    pub phy: PhyInfo,
+    pub presence: Presence,
+    pub gps: Option<Sightings>, // with a GPS receiver
+    pub nodes: Vec<Node>,
+    pub links: Vec<Link>,
 }
 
 impl Collection {
@@ -41,10 +59,16 @@ impl Collection {
             ssid: String::new(),
             protocol: PROTOCOL.into(),
             version: String::new(),
-            router_id: String::new(),
+            router_id: MacAddr::UNSPECIFIED,
             label: String::new(),
             signal: 0,
+            quality: SignalQuality::Unknown,
+            rssi: SignalStats::default(),
             current_channel: 0,
+            security: Security::default(),
+            phy: PhyInfo::default(),
+            presence: Presence::default(),
+            gps: None,
             nodes: Vec::new(),
             links: Vec::new(),
         }
@@ -59,35 +83,228 @@ impl Collection {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
-struct Node {
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Node {
     #[serde(rename = "id")]
-    pub mac: String,
+    pub mac: MacAddr,
     pub properties: Properties,
 }
 
 impl Node {
-    fn new(mac: String, vendor: String, signal: i8) -> Node {
-        let properties = Properties { vendor, signal };
+    fn new(mac: MacAddr, vendor: String, signal: i8, seen: u64) -> Node {
+        let properties = Properties {
+            vendor,
+            signal,
+            quality: SignalQuality::Unknown,
+            rssi: SignalStats::default(),
+            presence: Presence::new(seen),
+            traffic: TrafficStats::default(),
+            handshake: None,
+            connection: None,
+            events: Vec::new(),
+        };
 
         Node { mac, properties }
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
-struct Properties {
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Properties {
     pub vendor: String,
-    pub signal: i8,
+    pub signal: i8, // smoothed, see rssi
+    pub quality: SignalQuality,
+    pub rssi: SignalStats,
+    pub presence: Presence,
+    pub traffic: TrafficStats,
+    pub handshake: Option<HandshakeState>, // None until an EAPOL-Key frame is seen
+    pub connection: Option<ConnectionState>, // None until a connection event is seen
+    pub events: Vec<ConnectionEvent>,
+}
+
+impl Properties {
+    fn record_signal(&mut self, signal: i8, antennas: &[AntennaSignal]) {
+        self.rssi.record(signal, antennas);
+        self.signal = self.rssi.rssi.signal();
+        self.quality = self.rssi.rssi.quality();
+    }
+
+    fn record_event(&mut self, event: ConnectionEvent) {
+        if let Some(state) = event.kind.state() {
+            self.connection = Some(state);
+        }
+        if self.events.len() == MAX_CONNECTION_EVENTS {
+            self.events.remove(0);
+        }
+        self.events.push(event);
+    }
+}
+
+// When a device was seen and how many frames it sent
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Presence {
+    pub first_seen: u64, // seconds since the Unix epoch
+    pub last_seen: u64,
+    pub frames: u64,
 }
 
+impl Presence {
+    pub fn new(seen: u64) -> Presence {
+        Presence {
+            first_seen: seen,
+            last_seen: seen,
+            frames: 0,
+        }
+    }
+
+    // Frames addressed to a device show it is around, only the ones it sent are counted
+    pub fn seen(&mut self, seen: u64, sent: bool) {
+        self.last_seen = self.last_seen.max(seen);
+        if sent {
+            self.frames += 1;
+        }
+    }
+
+    fn expired(&self, now: u64, expiry: Duration) -> bool {
+        now.saturating_sub(self.last_seen) >= expiry.as_secs()
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DeviceKind {
+    AccessPoint,
+    Client,
+    Person,
+}
+
+// A device not seen for the expiry time, removed from the maps
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
-struct Link {
-    pub source: String,
-    pub target: String,
+pub struct Departure {
+    pub mac: MacAddr,
+    pub kind: DeviceKind,
+    pub bssid: Option<MacAddr>, // the access point of a client
+    pub presence: Presence,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ConnectionState {
+    Authenticated,
+    Associated,
+    Disassociated,
+    Deauthenticated,
+}
+
+// Authentication, association and disconnection frames exchanged by a client
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ConnectionEventKind {
+    Authentication {
+        algorithm: AuthAlgorithm,
+        sequence: u16,
+        status: StatusCode,
+    },
+    AssociationRequest,
+    AssociationResponse(StatusCode),
+    ReassociationRequest,
+    ReassociationResponse(StatusCode),
+    Deauthentication {
+        reason: ReasonCode,
+        from_client: bool,
+    },
+    Disassociation {
+        reason: ReasonCode,
+        from_client: bool,
+    },
+}
+
+impl ConnectionEventKind {
+    fn from_header(header: &Dot11Header, from_client: bool) -> Option<ConnectionEventKind> {
+        let kind = match header.info {
+            BodyInformation::Authentication(ref auth) => ConnectionEventKind::Authentication {
+                algorithm: auth.algorithm,
+                sequence: auth.sequence,
+                status: auth.status,
+            },
+            BodyInformation::AssociationRequest(_) => ConnectionEventKind::AssociationRequest,
+            BodyInformation::AssociationResponse(ref response) => {
+                ConnectionEventKind::AssociationResponse(StatusCode::from_code(
+                    response.status_code,
+                ))
+            }
+            BodyInformation::ReassociationRequest(_) => ConnectionEventKind::ReassociationRequest,
+            BodyInformation::ReassociationResponse(ref response) => {
+                ConnectionEventKind::ReassociationResponse(StatusCode::from_code(
+                    response.status_code,
+                ))
+            }
+            BodyInformation::Deauthentication(ref deauth) => {
+                ConnectionEventKind::Deauthentication {
+                    reason: deauth.reason,
+                    from_client,
+                }
+            }
+            BodyInformation::Disassociation(ref disassoc) => ConnectionEventKind::Disassociation {
+                reason: disassoc.reason,
+                from_client,
+            },
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    // The state the client is in after this event, if it changed
+    pub fn state(&self) -> Option<ConnectionState> {
+        match *self {
+            ConnectionEventKind::Authentication {
+                algorithm,
+                sequence,
+                status,
+            } if status == StatusCode::Success && sequence == algorithm.final_sequence() => {
+                Some(ConnectionState::Authenticated)
+            }
+            ConnectionEventKind::AssociationResponse(StatusCode::Success)
+            | ConnectionEventKind::ReassociationResponse(StatusCode::Success) => {
+                Some(ConnectionState::Associated)
+            }
+            ConnectionEventKind::Disassociation { .. } => Some(ConnectionState::Disassociated),
+            ConnectionEventKind::Deauthentication { .. } => Some(ConnectionState::Deauthenticated),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ConnectionEvent {
+    pub timestamp: u64, // seconds since the Unix epoch
+    pub kind: ConnectionEventKind,
+}
+
+// QoS data frames sent or received by a client, per access category
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TrafficStats {
+    pub background: u64,
+    pub best_effort: u64,
+    pub video: u64,
+    pub voice: u64,
+}
+
+impl TrafficStats {
+    pub fn count(&mut self, category: AccessCategory) {
+        match category {
+            AccessCategory::Background => self.background += 1,
+            AccessCategory::BestEffort => self.best_effort += 1,
+            AccessCategory::Video => self.video += 1,
+            AccessCategory::Voice => self.voice += 1,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Link {
+    pub source: MacAddr,
+    pub target: MacAddr,
 }
 
 impl Link {
-    fn new(src: String, dst: String) -> Link {
+    fn new(src: MacAddr, dst: MacAddr) -> Link {
         Link {
             source: src,
             target: dst,
@@ -97,40 +314,157 @@ impl Link {
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct People {
-    pub mac: String,
+    pub mac: MacAddr,
     pub vendor: String,
-    pub signal: i8,
-    pub distance: f32,
+    pub signal: i8, // smoothed, see rssi
+    pub quality: SignalQuality,
+    pub rssi: SignalStats,
+    pub distance: f32, // from the smoothed signal
+    pub presence: Presence,
+}
+
+// The frame being mapped, as received by the capturing adapter
+struct Reception<'a> {
+    signal: Option<i8>, // dBm
+    antennas: &'a [AntennaSignal],
+    seen: u64, // seconds since the Unix epoch
+    fix: Option<Fix>,
+}
+
+impl<'a> Reception<'a> {
+    // 0 when the capture has no signal, or for the receiver of the frame
+    fn signal(&self, sent: bool) -> i8 {
+        match self.signal {
+            Some(signal) if sent => signal,
+            _ => 0,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Mapper {
     pub vendors: VendorsDB,
-    pub net_map: HashMap<String, Collection>,
-    pub people_map: HashMap<String, People>,
+    pub net_map: HashMap<MacAddr, Collection>,
+    pub people_map: HashMap<MacAddr, People>,
+    pub expiry: Option<Duration>, // None keeps every device seen during the scan
+    pub distance: DistanceModel,
+    pub gps: Option<Gps>, // tags the access points with the position of the capture
+    departures: Vec<Departure>,
+    last_expiry: Duration,
 }
 
 impl Mapper {
     pub fn new() -> Result<Mapper> {
         let vendors = VendorsDB::from_file("data/oui.txt")?;
 
-        Ok(Mapper {
+        Ok(Mapper::with_vendors(vendors))
+    }
+
+    pub fn with_vendors(vendors: VendorsDB) -> Mapper {
+        Mapper {
             vendors,
             net_map: HashMap::new(),
             people_map: HashMap::new(),
-        })
+            expiry: None,
+            distance: DistanceModel::default(),
+            gps: None,
+            departures: Vec::new(),
+            last_expiry: Duration::default(),
+        }
+    }
+
+    // Devices that departed since the last call
+    pub fn take_departures(&mut self) -> Vec<Departure> {
+        std::mem::take(&mut self.departures)
+    }
+
+    // Removes the devices not seen for the expiry time, so only the ones still
+    // present are reported
+    pub fn expire(&mut self, timestamp: Duration) {
+        let expiry = match self.expiry {
+            Some(expiry) if timestamp.saturating_sub(self.last_expiry) >= EXPIRY_SWEEP => expiry,
+            _ => return,
+        };
+        self.last_expiry = timestamp;
+        let now = timestamp.as_secs();
+        let departures = &mut self.departures;
+
+        self.net_map.retain(|bssid, access_point| {
+            let gone = access_point.presence.expired(now, expiry);
+            access_point.nodes.retain(|node| {
+                // The node of the access point itself goes with the collection
+                if node.mac == *bssid {
+                    return !gone;
+                }
+                if gone || node.properties.presence.expired(now, expiry) {
+                    departures.push(Departure {
+                        mac: node.mac,
+                        kind: DeviceKind::Client,
+                        bssid: Some(*bssid),
+                        presence: node.properties.presence,
+                    });
+                    return false;
+                }
+                true
+            });
+            let nodes = &access_point.nodes;
+            access_point.links.retain(|link| {
+                nodes.iter().any(|node| node.mac == link.source)
+                    && nodes.iter().any(|node| node.mac == link.target)
+            });
+            if gone {
+                departures.push(Departure {
+                    mac: *bssid,
+                    kind: DeviceKind::AccessPoint,
+                    bssid: None,
+                    presence: access_point.presence,
+                });
+            }
+            !gone
+        });
+
+        self.people_map.retain(|mac, person| {
+            let gone = person.presence.expired(now, expiry);
+            if gone {
+                departures.push(Departure {
+                    mac: *mac,
+                    kind: DeviceKind::Person,
+                    bssid: None,
+                    presence: person.presence,
+                });
+            }
+            !gone
+        });
     }
 
     pub fn map(
         &mut self, radio_header: Radiotap, dot11_header: Dot11Header, people: bool,
     ) -> Option<Collection> {
+        let signals = RadiotapSignals::default();
+        self.map_at(radio_header, &signals, dot11_header, people, unix_time())
+    }
+
+    // timestamp is the capture time of the frame, relative to the Unix epoch. The
+    // signals read from the raw radiotap header add the per antenna values.
+    pub fn map_at(
+        &mut self, radio_header: Radiotap, signals: &RadiotapSignals, dot11_header: Dot11Header,
+        people: bool, timestamp: Duration,
+    ) -> Option<Collection> {
+        self.expire(timestamp);
+
         let mut new_ap = None;
         let info = dot11_header.info.clone();
         let frame_type = dot11_header.frame_control.frame_type;
         let frame_subtype = dot11_header.frame_control.frame_subtype;
-        let signal = match radio_header.antenna_signal {
-            Some(antenna_signal) => antenna_signal.value,
-            None => 0,
+        let rx = Reception {
+            signal: signals.combined.or_else(|| {
+                radio_header
+                    .antenna_signal
+                    .map(|antenna_signal| antenna_signal.value)
+            }),
+            antennas: &signals.antennas,
+            seen: timestamp.as_secs(),
+            fix: self.gps.as_ref().and_then(|gps| gps.fix_at(timestamp)),
         };
 
         let freq: f32 = match radio_header.channel {
@@ -140,50 +474,65 @@ impl Mapper {
 
         if people {
             if frame_type == FrameType::Management && frame_subtype == FrameSubType::ProbeReq {
-                self.add_people(dot11_header.src, freq, signal);
+                self.add_people(dot11_header.src, freq, &rx);
             }
         } else {
             // We should monitor the Probe Request frames for positioning information
             if frame_type == FrameType::Management && frame_subtype == FrameSubType::ProbeReq {
-                self.add_to_collection(dot11_header.src, dot11_header.bssid, signal);
+                self.add_to_collection(dot11_header.src, dot11_header.bssid, &rx, true);
             } else if frame_type == FrameType::Data {
-                if frame_subtype == FrameSubType::QoS || frame_subtype == FrameSubType::Data {
-                    self.add_to_collection(dot11_header.src, dot11_header.bssid.clone(), signal);
-                    self.add_to_collection(dot11_header.dst, dot11_header.bssid, signal);
-                } else if frame_subtype == FrameSubType::NullData {
-                    // NullData informs Device Power Serving mode
-                    self.add_to_collection(dot11_header.dst, dot11_header.bssid, signal);
+                // NullData informs Device Power Serving mode
+                if frame_subtype == FrameSubType::QoS
+                    || frame_subtype == FrameSubType::Data
+                    || frame_subtype == FrameSubType::NullData
+                    || frame_subtype == FrameSubType::QoSNullData
+                {
+                    self.add_stations(&dot11_header, &rx);
+                }
+                if let BodyInformation::EapolKey(ref key) = info {
+                    self.add_handshake_message(&dot11_header, key);
+                }
+            } else if frame_type == FrameType::Control {
+                // RTS, Block Ack and PS-Poll reveal stations that may never be seen sending data
+                if !dot11_header.transmitter.is_unspecified() {
+                    self.add_stations(&dot11_header, &rx);
                 }
             } else if frame_type == FrameType::Management {
                 // Lets use the Beacon frame to get Access Point information
                 if let BodyInformation::Beacon(beacon) = info {
-                    new_ap = self.add_access_point(beacon, signal, dot11_header);
+                    new_ap = self.add_access_point(beacon, &dot11_header, &rx);
+                } else {
+                    self.add_connection_event(&dot11_header, &rx);
                 }
             }
+            self.touch_access_point(&dot11_header, &rx);
         }
         new_ap
     }
 
     fn add_access_point(
-        &mut self, beacon: Beacon, signal: i8, dot11_header: Dot11Header,
+        &mut self, beacon: Beacon, dot11_header: &Dot11Header, rx: &Reception,
     ) -> Option<Collection> {
-        if !dot11_header.bssid.contains(BROADCAST) && !dot11_header.bssid.contains(UNSPECIFIED) {
+        if !dot11_header.bssid.is_broadcast() && !dot11_header.bssid.is_unspecified() {
             let header = dot11_header;
-            if !self.net_map.contains_key(&header.bssid.clone()) {
+            if !self.net_map.contains_key(&header.bssid) {
                 let mut access_point = Collection::new();
 
                 access_point.ssid = beacon.ssid.value.clone();
-                access_point.signal = signal;
+                access_point.signal = rx.signal(true);
                 access_point.current_channel = beacon.current_channel;
-                access_point.router_id = header.bssid.clone();
-                access_point.label = self.vendors.lookup(header.bssid.clone());
+                access_point.security = beacon.security.clone();
+                access_point.phy = beacon.phy.clone();
+                access_point.router_id = header.bssid;
+                access_point.presence = Presence::new(rx.seen);
+                access_point.label = self.vendors.lookup(header.bssid);
 
                 // TODO: Check why we get some empty SSIDs
                 if access_point.ssid == "" {
                     return None;
                 }
 
-                let node = Node::new(header.bssid.clone(), access_point.label.clone(), 0);
+                let node = Node::new(header.bssid, access_point.label.clone(), 0, rx.seen);
                 access_point.nodes.push(node);
                 self.net_map.insert(header.bssid, access_point.clone());
                 return Some(access_point);
@@ -192,15 +541,56 @@ impl Mapper {
         None
     }
 
-    fn add_to_collection(&mut self, mac: String, bssid: String, signal: i8) {
-        if !mac.contains(BROADCAST) && !mac.starts_with(MULTICAST) {
-            let node = self.add_node(mac.clone(), signal);
-            let link = Link::new(mac, bssid.clone());
+    // Frames sent by the access point itself, beacons included
+    fn touch_access_point(&mut self, header: &Dot11Header, rx: &Reception) {
+        if header.transmitter != header.bssid {
+            return;
+        }
+        if let Some(access_point) = self.net_map.get_mut(&header.bssid) {
+            access_point.presence.seen(rx.seen, true);
+            if let Some(signal) = rx.signal {
+                access_point.rssi.record(signal, rx.antennas);
+                access_point.signal = access_point.rssi.rssi.signal();
+                access_point.quality = access_point.rssi.rssi.quality();
+            }
+            if let Some(ref fix) = rx.fix {
+                let signal = rx.signal(true);
+                match access_point.gps {
+                    Some(ref mut sightings) => sightings.record(fix.clone(), signal),
+                    None => access_point.gps = Some(Sightings::new(fix.clone(), signal)),
+                }
+            }
+            if let Some(node) = access_point
+                .nodes
+                .iter_mut()
+                .find(|node| node.mac == header.bssid)
+            {
+                node.properties.presence.seen(rx.seen, true);
+                if let Some(signal) = rx.signal {
+                    node.properties.record_signal(signal, rx.antennas);
+                }
+            }
+        }
+    }
+
+    // The signal and the frame count belong to the transmitter, sent is false for the
+    // receiver of the frame
+    fn add_to_collection(&mut self, mac: MacAddr, bssid: MacAddr, rx: &Reception, sent: bool) {
+        if !mac.is_group() && !mac.is_unspecified() && mac != bssid {
+            let node = self.add_node(mac, rx.signal(sent), rx.seen);
+            let link = Link::new(mac, bssid);
             if let Some(access_point) = self.net_map.get_mut(&bssid) {
                 let mut node_iter = access_point.nodes.clone().into_iter();
                 if node_iter.find(|ref mut x| x.mac == node.mac) == None {
                     access_point.push_node(node);
                 }
+                if let Some(node) = access_point.nodes.iter_mut().find(|node| node.mac == mac) {
+                    node.properties.presence.seen(rx.seen, sent);
+                    match rx.signal {
+                        Some(signal) if sent => node.properties.record_signal(signal, rx.antennas),
+                        _ => {}
+                    }
+                }
 
                 if !access_point.links.contains(&link) {
                     access_point.push_link(link);
@@ -209,12 +599,109 @@ impl Mapper {
         }
     }
 
-    fn add_node(&mut self, mac: String, signal: i8) -> Node {
-        let vendor = self.vendors.lookup(mac.clone());
-        Node::new(mac, vendor, signal)
+    // Links the stations at both ends of the wireless hop, the signal belongs to the transmitter
+    fn add_stations(&mut self, header: &Dot11Header, rx: &Reception) {
+        let fc = header.frame_control;
+        let stations = if fc.frame_type == FrameType::Control || (fc.to_ds && fc.from_ds) {
+            // Either end may be the access point, or both for WDS bridges and mesh links
+            vec![
+                (header.transmitter, header.receiver, true),
+                (header.receiver, header.transmitter, false),
+            ]
+        } else if fc.to_ds {
+            vec![(header.transmitter, header.bssid, true)]
+        } else if fc.from_ds {
+            vec![(header.receiver, header.bssid, false)]
+        } else {
+            vec![
+                (header.src, header.bssid, true),
+                (header.dst, header.bssid, false),
+            ]
+        };
+
+        // QoS Null frames carry no traffic
+        let category = match header.qos {
+            Some(qos) if fc.frame_subtype != FrameSubType::QoSNullData => {
+                Some(qos.access_category())
+            }
+            _ => None,
+        };
+
+        for (mac, bssid, sent) in stations {
+            self.add_to_collection(mac, bssid, rx, sent);
+            if let Some(category) = category {
+                self.count_traffic(mac, bssid, category);
+            }
+        }
+    }
+
+    fn count_traffic(&mut self, mac: MacAddr, bssid: MacAddr, category: AccessCategory) {
+        if let Some(access_point) = self.net_map.get_mut(&bssid) {
+            if let Some(node) = access_point.nodes.iter_mut().find(|node| node.mac == mac) {
+                node.properties.traffic.count(category);
+            }
+        }
+    }
+
+    fn add_connection_event(&mut self, header: &Dot11Header, rx: &Reception) {
+        let from_client = header.transmitter != header.bssid;
+        let kind = match ConnectionEventKind::from_header(header, from_client) {
+            Some(kind) => kind,
+            None => return,
+        };
+        let event = ConnectionEvent {
+            timestamp: rx.seen,
+            kind,
+        };
+
+        if from_client {
+            self.add_to_collection(header.transmitter, header.bssid, rx, true);
+        } else if !header.receiver.is_group() {
+            self.add_to_collection(header.receiver, header.bssid, rx, false);
+        }
+
+        if let Some(access_point) = self.net_map.get_mut(&header.bssid) {
+            // A broadcast deauthentication or disassociation disconnects every client
+            let client = if from_client {
+                header.transmitter
+            } else {
+                header.receiver
+            };
+            for node in access_point.nodes.iter_mut() {
+                if node.mac != header.bssid && (node.mac == client || client.is_broadcast()) {
+                    node.properties.record_event(event);
+                }
+            }
+        }
     }
 
-    fn add_people(&mut self, src: String, freq: f32, signal: i8) {
+    fn add_handshake_message(&mut self, header: &Dot11Header, key: &EapolKey) {
+        // The access point sends the frames that ask for an acknowledgement (M1 and M3)
+        let client = if header.transmitter == header.bssid {
+            header.receiver
+        } else {
+            header.transmitter
+        };
+        if let Some(access_point) = self.net_map.get_mut(&header.bssid) {
+            if let Some(node) = access_point
+                .nodes
+                .iter_mut()
+                .find(|node| node.mac == client)
+            {
+                node.properties
+                    .handshake
+                    .get_or_insert_with(HandshakeState::default)
+                    .record(key);
+            }
+        }
+    }
+
+    fn add_node(&mut self, mac: MacAddr, signal: i8, seen: u64) -> Node {
+        let vendor = self.vendors.lookup(mac);
+        Node::new(mac, vendor, signal, seen)
+    }
+
+    fn add_people(&mut self, src: MacAddr, freq: f32, rx: &Reception) {
         // Add phone vendors here but move for a Lazy Load initialization later
         let phone_vendors = vec![
             "Samsung Electronics Co.,Ltd",
@@ -227,23 +714,29 @@ impl Mapper {
         ];
 
         // Get Mobile Phone vendor
-        let vendor = self.vendors.lookup(src.clone());
+        let vendor = self.vendors.lookup(src);
         if phone_vendors.contains(&vendor.as_str()) {
-            let distance = self.calc_distance(freq, signal);
+            let (mut presence, mut rssi) = match self.people_map.remove(&src) {
+                Some(person) => (person.presence, person.rssi),
+                None => (Presence::new(rx.seen), SignalStats::default()),
+            };
+            presence.seen(rx.seen, true);
+            if let Some(signal) = rx.signal {
+                rssi.record(signal, rx.antennas);
+            }
+            // The smoothed signal keeps the distance from jumping with every sample
+            let signal = rssi.rssi.signal();
+            let distance = self.distance.distance(signal, freq, &vendor);
             let person = People {
-                mac: src.clone(),
+                mac: src,
                 vendor,
                 signal,
+                quality: rssi.rssi.quality(),
+                rssi,
                 distance,
+                presence,
             };
             self.people_map.insert(src, person);
         }
     }
-
-    // https://en.wikipedia.org/wiki/Free-space_path_loss
-    fn calc_distance(&mut self, freq: f32, signal: i8) -> f32 {
-        let value = 10_f32;
-        let expr = (FREE_SPACE_PATH_LOSS - (20.0 * freq.log10()) + (-signal as f32)) / 20.0;
-        value.powf(expr)
-    }
 }
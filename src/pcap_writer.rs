@@ -0,0 +1,111 @@
+use crate::errors::*;
+use pcap::{Capture, Dead, Linktype, Packet, PacketHeader, Savefile};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const PCAP_FILE_HEADER_LEN: u64 = 24;
+const PCAP_RECORD_HEADER_LEN: u64 = 16;
+
+// When the current file should be closed and a new one started
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Rotation {
+    pub max_size: Option<u64>, // bytes
+    pub max_time: Option<Duration>,
+}
+
+pub struct PcapWriter {
+    handle: Capture<Dead>,
+    savefile: Savefile,
+    path: PathBuf,
+    rotation: Rotation,
+    index: usize,
+    written: u64,
+    opened: Instant,
+}
+
+impl PcapWriter {
+    pub fn new<P>(path: P, linktype: Linktype, rotation: Rotation) -> Result<PcapWriter>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        // A dead handle lets us open new savefiles without touching the live capture
+        let handle = Capture::dead(linktype)?;
+        let savefile = handle.savefile(&path)?;
+
+        Ok(PcapWriter {
+            handle,
+            savefile,
+            path,
+            rotation,
+            index: 0,
+            written: PCAP_FILE_HEADER_LEN,
+            opened: Instant::now(),
+        })
+    }
+
+    pub fn write(&mut self, packet: &Packet) -> Result<()> {
+        if self.should_rotate() {
+            self.rotate()?;
+        }
+
+        self.savefile.write(packet);
+        self.written += PCAP_RECORD_HEADER_LEN + u64::from(packet.header.caplen);
+
+        Ok(())
+    }
+
+    // Writes a frame that wasn't captured, e.g. one built with the dot11 encoders.
+    // The timestamp is relative to the Unix epoch.
+    pub fn write_frame(&mut self, data: &[u8], timestamp: Duration) -> Result<()> {
+        let header = PacketHeader {
+            ts: libc::timeval {
+                tv_sec: timestamp.as_secs() as libc::time_t,
+                tv_usec: timestamp.subsec_micros() as libc::suseconds_t,
+            },
+            caplen: data.len() as u32,
+            len: data.len() as u32,
+        };
+
+        self.write(&Packet::new(&header, data))
+    }
+
+    fn should_rotate(&self) -> bool {
+        let size_exceeded = match self.rotation.max_size {
+            Some(max_size) => self.written >= max_size,
+            None => false,
+        };
+        let time_exceeded = match self.rotation.max_time {
+            Some(max_time) => self.opened.elapsed() >= max_time,
+            None => false,
+        };
+
+        size_exceeded || time_exceeded
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        self.index += 1;
+        let path = self.rotated_path();
+        // The previous file is flushed and closed when its Savefile is dropped
+        self.savefile = self.handle.savefile(&path)?;
+        self.written = PCAP_FILE_HEADER_LEN;
+        self.opened = Instant::now();
+
+        Ok(())
+    }
+
+    // capture.pcap => capture-1.pcap, capture-2.pcap, ...
+    fn rotated_path(&self) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let file_name = match self.path.extension() {
+            Some(ext) => format!("{}-{}.{}", stem, self.index, ext.to_string_lossy()),
+            None => format!("{}-{}", stem, self.index),
+        };
+
+        self.path.with_file_name(file_name)
+    }
+}
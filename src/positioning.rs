@@ -0,0 +1,366 @@
+use crate::errors::*;
+use crate::distance::DistanceModel;
+use crate::dot11::mac::MacAddr;
+use crate::mapper::{NetworkCollection, People};
+use crate::radiotap_writer::Channel;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+// Frequency assumed when an observation doesn't tell the channel
+const DEFAULT_FREQ: f32 = 2437.0;
+// Mean Earth radius used to project local meters to WGS84, in meters
+const EARTH_RADIUS: f64 = 6_371_008.8;
+const REFINE_ITERATIONS: usize = 10;
+
+// A capturing adapter at a known place of the deployment
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Sensor {
+    pub name: String,
+    pub x: f64, // meters
+    pub y: f64,
+    #[serde(default)]
+    pub output: Option<PathBuf>, // JSON written by its Mapper: netjson or people
+}
+
+// The sensors and how their signals turn into distances
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Deployment {
+    pub sensors: Vec<Sensor>,
+    pub origin: Option<[f64; 2]>, // longitude and latitude of (0, 0), None keeps meters
+    pub distance: DistanceModel,
+}
+
+impl Deployment {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Deployment> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn sensor(&self, name: &str) -> Option<&Sensor> {
+        self.sensors.iter().find(|sensor| sensor.name == name)
+    }
+
+    // Equirectangular projection, good enough over the size of a building
+    fn coordinates(&self, x: f64, y: f64) -> [f64; 2] {
+        match self.origin {
+            Some([longitude, latitude]) => [
+                longitude + (x / (EARTH_RADIUS * latitude.to_radians().cos())).to_degrees(),
+                latitude + (y / EARTH_RADIUS).to_degrees(),
+            ],
+            None => [x, y],
+        }
+    }
+}
+
+// A device heard by one sensor
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Observation {
+    pub sensor: String,
+    pub mac: MacAddr,
+    pub signal: i8, // dBm
+    #[serde(default)]
+    pub vendor: String,
+    #[serde(default)]
+    pub freq: Option<f32>, // MHz
+}
+
+impl Observation {
+    // One JSON observation per line
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Vec<Observation>> {
+        let mut observations = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                observations.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(observations)
+    }
+}
+
+// The mean signal of a device at one sensor
+#[derive(Clone, Debug, Default)]
+struct Heard {
+    signal_sum: f32,
+    samples: u32,
+    freq: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Position {
+    pub mac: MacAddr,
+    pub vendor: String,
+    pub x: f64, // meters
+    pub y: f64,
+    pub uncertainty: f64, // meters, RMS of the distance residuals
+    pub sensors: usize,
+}
+
+// Fuses the observations of several sensors into device positions
+#[derive(Clone, Debug, Default)]
+pub struct Trilateration {
+    pub deployment: Deployment,
+    heard: BTreeMap<MacAddr, BTreeMap<String, Heard>>,
+    vendors: BTreeMap<MacAddr, String>,
+}
+
+impl Trilateration {
+    pub fn new(deployment: Deployment) -> Trilateration {
+        Trilateration {
+            deployment,
+            ..Default::default()
+        }
+    }
+
+    pub fn observe(&mut self, observation: &Observation) -> Result<()> {
+        if self.deployment.sensor(&observation.sensor).is_none() {
+            bail!(ErrorKind::UnknownSensor(observation.sensor.clone()));
+        }
+        // 0 dBm: the capture had no signal
+        if observation.signal == 0 {
+            return Ok(());
+        }
+
+        let heard = self
+            .heard
+            .entry(observation.mac)
+            .or_default()
+            .entry(observation.sensor.clone())
+            .or_default();
+        heard.signal_sum += f32::from(observation.signal);
+        heard.samples += 1;
+        heard.freq = observation.freq.unwrap_or(DEFAULT_FREQ);
+        if !observation.vendor.is_empty() {
+            self.vendors
+                .insert(observation.mac, observation.vendor.clone());
+        }
+        Ok(())
+    }
+
+    // The access points and clients of a netjson
+    pub fn add_netjson(&mut self, sensor: &str, networks: &NetworkCollection) -> Result<()> {
+        for access_point in &networks.collection {
+            let freq = f32::from(Channel::from_number(access_point.current_channel).frequency);
+            for node in &access_point.nodes {
+                self.observe(&Observation {
+                    sensor: sensor.to_string(),
+                    mac: node.mac,
+                    signal: node.properties.signal,
+                    vendor: node.properties.vendor.clone(),
+                    freq: Some(freq).filter(|_| access_point.current_channel != 0),
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn add_people(&mut self, sensor: &str, people: &[People]) -> Result<()> {
+        for person in people {
+            self.observe(&Observation {
+                sensor: sensor.to_string(),
+                mac: person.mac,
+                signal: person.signal,
+                vendor: person.vendor.clone(),
+                freq: None,
+            })?;
+        }
+        Ok(())
+    }
+
+    // Reads the output of every sensor that has one configured
+    pub fn load_outputs(&mut self) -> Result<()> {
+        for sensor in self.deployment.sensors.clone() {
+            let path = match sensor.output {
+                Some(ref path) => path,
+                None => continue,
+            };
+            let content = fs::read_to_string(path)?;
+            match serde_json::from_str::<NetworkCollection>(&content) {
+                Ok(networks) => self.add_netjson(&sensor.name, &networks)?,
+                Err(_) => {
+                    let people: Vec<People> = serde_json::from_str(&content)?;
+                    self.add_people(&sensor.name, &people)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Devices heard by three sensors at least
+    pub fn positions(&self) -> Vec<Position> {
+        let mut positions = Vec::new();
+        for (mac, heard) in &self.heard {
+            let vendor = self.vendors.get(mac).cloned().unwrap_or_default();
+            let anchors: Vec<Anchor> = heard
+                .iter()
+                .filter_map(|(name, heard)| {
+                    let sensor = self.deployment.sensor(name)?;
+                    let signal = (heard.signal_sum / heard.samples as f32).round() as i8;
+                    let distance = self
+                        .deployment
+                        .distance
+                        .distance(signal, heard.freq, &vendor);
+                    Some(Anchor {
+                        x: sensor.x,
+                        y: sensor.y,
+                        distance: f64::from(distance),
+                    })
+                })
+                .collect();
+
+            if let Some((x, y, uncertainty)) = trilaterate(&anchors) {
+                positions.push(Position {
+                    mac: *mac,
+                    vendor,
+                    x,
+                    y,
+                    uncertainty,
+                    sensors: anchors.len(),
+                });
+            }
+        }
+        positions
+    }
+
+    pub fn geojson(&self) -> FeatureCollection {
+        let features = self
+            .positions()
+            .into_iter()
+            .map(|position| Feature {
+                kind: "Feature".into(),
+                geometry: Geometry {
+                    kind: "Point".into(),
+                    coordinates: self.deployment.coordinates(position.x, position.y),
+                },
+                properties: PositionProperties {
+                    mac: position.mac,
+                    vendor: position.vendor,
+                    uncertainty: position.uncertainty,
+                    sensors: position.sensors,
+                },
+            })
+            .collect();
+
+        FeatureCollection {
+            kind: "FeatureCollection".into(),
+            features,
+        }
+    }
+}
+
+// A sensor and the estimated distance of the device
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Anchor {
+    pub x: f64,
+    pub y: f64,
+    pub distance: f64,
+}
+
+// Least squares position and RMS residual. The circles are linearized against the
+// last anchor, then refined with Gauss-Newton. None with fewer than three anchors
+// or when they are aligned.
+pub fn trilaterate(anchors: &[Anchor]) -> Option<(f64, f64, f64)> {
+    if anchors.len() < 3 {
+        return None;
+    }
+
+    let last = anchors[anchors.len() - 1];
+    let rows: Vec<(f64, f64, f64)> = anchors[..anchors.len() - 1]
+        .iter()
+        .map(|anchor| {
+            (
+                2.0 * (last.x - anchor.x),
+                2.0 * (last.y - anchor.y),
+                anchor.distance.powi(2) - last.distance.powi(2) - anchor.x.powi(2) + last.x.powi(2)
+                    - anchor.y.powi(2)
+                    + last.y.powi(2),
+            )
+        })
+        .collect();
+    let (mut x, mut y) = solve(&rows)?;
+
+    for _ in 0..REFINE_ITERATIONS {
+        let rows: Vec<(f64, f64, f64)> = anchors
+            .iter()
+            .filter_map(|anchor| {
+                let range = (x - anchor.x).hypot(y - anchor.y);
+                if range < f64::EPSILON {
+                    return None;
+                }
+                Some((
+                    (x - anchor.x) / range,
+                    (y - anchor.y) / range,
+                    anchor.distance - range,
+                ))
+            })
+            .collect();
+        match solve(&rows) {
+            Some((dx, dy)) => {
+                x += dx;
+                y += dy;
+            }
+            None => break,
+        }
+    }
+
+    let residuals: f64 = anchors
+        .iter()
+        .map(|anchor| ((x - anchor.x).hypot(y - anchor.y) - anchor.distance).powi(2))
+        .sum();
+    Some((x, y, (residuals / anchors.len() as f64).sqrt()))
+}
+
+// Normal equations of a x + b y = c
+fn solve(rows: &[(f64, f64, f64)]) -> Option<(f64, f64)> {
+    let (mut aa, mut ab, mut bb, mut ac, mut bc) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for (a, b, c) in rows {
+        aa += a * a;
+        ab += a * b;
+        bb += b * b;
+        ac += a * c;
+        bc += b * c;
+    }
+    let determinant = aa * bb - ab * ab;
+    if determinant.abs() < 1e-9 {
+        return None;
+    }
+    Some((
+        (ac * bb - ab * bc) / determinant,
+        (aa * bc - ab * ac) / determinant,
+    ))
+}
+
+// https://tools.ietf.org/html/rfc7946
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FeatureCollection {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub features: Vec<Feature>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Feature {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub geometry: Geometry,
+    pub properties: PositionProperties,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Geometry {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub coordinates: [f64; 2], // longitude and latitude, or meters without an origin
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PositionProperties {
+    pub mac: MacAddr,
+    pub vendor: String,
+    pub uncertainty: f64, // meters
+    pub sensors: usize,
+}
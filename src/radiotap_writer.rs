@@ -0,0 +1,123 @@
+use crate::dot11::writer::{Encode, Writer};
+
+const RADIOTAP_HEADER_LEN: usize = 8;
+
+// Present flags, https://www.radiotap.org/fields/defined
+const TSFT_PRESENT: u32 = 1 << 0;
+const FLAGS_PRESENT: u32 = 1 << 1;
+const RATE_PRESENT: u32 = 1 << 2;
+const CHANNEL_PRESENT: u32 = 1 << 3;
+const ANTENNA_SIGNAL_PRESENT: u32 = 1 << 5;
+const ANTENNA_NOISE_PRESENT: u32 = 1 << 6;
+const ANTENNA_PRESENT: u32 = 1 << 11;
+
+// Channel flags
+const CHANNEL_CCK: u16 = 0x0020;
+const CHANNEL_OFDM: u16 = 0x0040;
+const CHANNEL_2GHZ: u16 = 0x0080;
+const CHANNEL_5GHZ: u16 = 0x0100;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Channel {
+    pub frequency: u16, // MHz
+    pub flags: u16,
+}
+
+impl Channel {
+    // 2.4 GHz channels 1-14 and 5 GHz channels 32-177
+    pub fn from_number(channel: u8) -> Channel {
+        match channel {
+            14 => Channel {
+                frequency: 2484,
+                flags: CHANNEL_2GHZ | CHANNEL_CCK,
+            },
+            1..=13 => Channel {
+                frequency: 2407 + 5 * u16::from(channel),
+                flags: CHANNEL_2GHZ | CHANNEL_OFDM,
+            },
+            _ => Channel {
+                frequency: 5000 + 5 * u16::from(channel),
+                flags: CHANNEL_5GHZ | CHANNEL_OFDM,
+            },
+        }
+    }
+}
+
+// Radiotap header prepended to the 802.11 frames we generate, only the fields
+// that are set are written
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RadiotapHeader {
+    pub tsft: Option<u64>, // microseconds
+    pub flags: Option<u8>,
+    pub rate: Option<f32>, // Mbps
+    pub channel: Option<Channel>,
+    pub antenna_signal: Option<i8>, // dBm
+    pub antenna_noise: Option<i8>,  // dBm
+    pub antenna: Option<u8>,
+}
+
+impl RadiotapHeader {
+    // Radiotap header followed by the frame, ready to be written to a capture
+    pub fn encapsulate(&self, frame: &[u8]) -> Vec<u8> {
+        let mut writer = Writer::new();
+        self.encode(&mut writer);
+        writer.bytes(frame);
+        writer.into_bytes()
+    }
+
+    fn present(&self) -> u32 {
+        [
+            (self.tsft.is_some(), TSFT_PRESENT),
+            (self.flags.is_some(), FLAGS_PRESENT),
+            (self.rate.is_some(), RATE_PRESENT),
+            (self.channel.is_some(), CHANNEL_PRESENT),
+            (self.antenna_signal.is_some(), ANTENNA_SIGNAL_PRESENT),
+            (self.antenna_noise.is_some(), ANTENNA_NOISE_PRESENT),
+            (self.antenna.is_some(), ANTENNA_PRESENT),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |present, (_, flag)| present | flag)
+    }
+}
+
+// Fields are written in present bit order, aligned to their natural size
+impl Encode for RadiotapHeader {
+    fn encode(&self, writer: &mut Writer) {
+        let mut fields = Writer::new();
+        // Alignment is relative to the start of the header
+        fields.bytes(&[0; RADIOTAP_HEADER_LEN]);
+
+        if let Some(tsft) = self.tsft {
+            fields.align(8);
+            fields.u64(tsft);
+        }
+        if let Some(flags) = self.flags {
+            fields.u8(flags);
+        }
+        if let Some(rate) = self.rate {
+            fields.u8((rate * 2.0) as u8); // 500 kbps units
+        }
+        if let Some(channel) = self.channel {
+            fields.align(2);
+            fields.u16(channel.frequency);
+            fields.u16(channel.flags);
+        }
+        if let Some(signal) = self.antenna_signal {
+            fields.u8(signal as u8);
+        }
+        if let Some(noise) = self.antenna_noise {
+            fields.u8(noise as u8);
+        }
+        if let Some(antenna) = self.antenna {
+            fields.u8(antenna);
+        }
+
+        let fields = fields.into_bytes();
+        writer.u8(0); // version
+        writer.u8(0); // padding
+        writer.u16(fields.len() as u16);
+        writer.u32(self.present());
+        writer.bytes(&fields[RADIOTAP_HEADER_LEN..]);
+    }
+}
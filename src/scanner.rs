@@ -0,0 +1,141 @@
+use crate::errors::*;
+use crate::dot11::header::*;
+use crate::handshake::HandshakeTracker;
+use crate::mapper::*;
+use crate::signal::RadiotapSignals;
+use crate::source::*;
+use crate::util::unix_time;
+use crate::wids::*;
+use radiotap::Radiotap;
+use std::time::{Duration, Instant};
+
+// What happened while processing one step of a scan
+#[derive(Clone, Debug)]
+pub enum ScanEvent {
+    AccessPoint(Box<Collection>), // a new access point was mapped
+    Frame,
+    Timeout,
+    Finished, // the source is exhausted or the execution window elapsed
+}
+
+// Feeds the frames of a PacketSource through the Mapper
+#[derive(Clone, Debug)]
+pub struct Scanner {
+    pub mapper: Mapper,
+    pub people: bool,
+    pub window: Option<Duration>,
+    pub malformed_frames: u64,
+    pub handshakes: HandshakeTracker,
+    pub wids: Wids,
+    start: Option<Instant>,
+}
+
+impl Scanner {
+    pub fn new(mapper: Mapper, people: bool, window: Option<Duration>) -> Scanner {
+        Scanner {
+            mapper,
+            people,
+            window,
+            malformed_frames: 0,
+            handshakes: HandshakeTracker::new(),
+            wids: Wids::new(),
+            start: None,
+        }
+    }
+
+    // Time left in the execution window, None when the scan is not time bounded
+    pub fn remaining(&self) -> Option<Duration> {
+        let window = self.window?;
+        match self.start {
+            Some(start) => Some(window.checked_sub(start.elapsed()).unwrap_or_default()),
+            None => Some(window),
+        }
+    }
+
+    pub fn step<S: PacketSource + ?Sized>(&mut self, source: &mut S) -> Result<ScanEvent> {
+        if self.start.is_none() {
+            self.start = Some(Instant::now());
+        }
+
+        if self.remaining() == Some(Duration::from_secs(0)) {
+            return Ok(ScanEvent::Finished);
+        }
+
+        match source.next_frame()? {
+            Next::Frame(data, timestamp) => match self.process_at(data, timestamp)? {
+                Some(ap) => Ok(ScanEvent::AccessPoint(Box::new(ap))),
+                None => Ok(ScanEvent::Frame),
+            },
+            // A quiet channel still ages the devices out
+            Next::Timeout => {
+                self.mapper.expire(unix_time());
+                Ok(ScanEvent::Timeout)
+            }
+            Next::Done => Ok(ScanEvent::Finished),
+        }
+    }
+
+    // Scans until the source is exhausted or the execution window elapsed
+    pub fn run<S: PacketSource + ?Sized>(&mut self, source: &mut S) -> Result<()> {
+        loop {
+            if let ScanEvent::Finished = self.step(source)? {
+                return Ok(());
+            }
+        }
+    }
+
+    // Processes a frame received now
+    pub fn process(&mut self, data: &[u8]) -> Result<Option<Collection>> {
+        self.process_at(data, unix_time())
+    }
+
+    // Malformed frames are counted and skipped, so they can't abort a long running scan
+    pub fn process_at(&mut self, data: &[u8], timestamp: Duration) -> Result<Option<Collection>> {
+        match Scanner::parse(data) {
+            Ok((tap_data, dot11_header)) => {
+                self.handshakes.capture(data, &dot11_header, timestamp);
+                self.wids.inspect(&tap_data, &dot11_header, timestamp);
+                // Fake access points mapped before the flood was detected are removed
+                for bssid in self.wids.take_quarantined() {
+                    self.mapper.net_map.remove(&bssid);
+                }
+                if self.wids.is_quarantined(dot11_header.bssid) {
+                    return Ok(None);
+                }
+                let signals = RadiotapSignals::from_bytes(data);
+                Ok(self
+                    .mapper
+                    .map_at(tap_data, &signals, dot11_header, self.people, timestamp))
+            }
+            Err(ref e) if e.is_malformed_frame() => {
+                self.malformed_frames += 1;
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // Devices that left since the last call, when the mapper has an expiry time
+    pub fn take_departures(&mut self) -> Vec<Departure> {
+        self.mapper.take_departures()
+    }
+
+    // Alerts raised by the intrusion detection since the last call
+    pub fn take_alerts(&mut self) -> Vec<Alert> {
+        self.wids.take_alerts()
+    }
+
+    pub fn parse(data: &[u8]) -> Result<(Radiotap, Dot11Header)> {
+        let tap_data = match Radiotap::from_bytes(data) {
+            Ok(tap_data) => tap_data,
+            Err(e) => bail!(ErrorKind::InvalidRadiotap(e.to_string())),
+        };
+
+        if tap_data.header.length > data.len() {
+            bail!(ErrorKind::Truncated(tap_data.header.length, data.len()));
+        }
+        let dot11_header = Dot11Header::from_bytes(&data[tap_data.header.length..])?;
+
+        Ok((tap_data, dot11_header))
+    }
+}
@@ -1,18 +1,24 @@
 use env_logger::init;
 use actix::System;
-use actix_web::{fs, middleware, server, App};
+use actix_web::{fs, middleware, server, App, HttpResponse};
+use nearby::wids::SharedAlerts;
 
-pub fn start() {
+// Serves the static visualization and the latest alerts on /alerts
+pub fn start(alerts: SharedAlerts) {
     ::std::env::set_var("RUST_LOG", "actix_web=info");
     ::std::env::set_var("RUST_BACKTRACE", "1");
     init();
 
     let sys = System::new("static_index");
 
-    server::new(|| {
+    server::new(move || {
+        let alerts = alerts.clone();
         App::new()
             // enable logger
             .middleware(middleware::Logger::default())
+            .resource("/alerts", move |r| {
+                r.f(move |_| HttpResponse::Ok().json(alerts.snapshot()))
+            })
             .handler(
                 "/",
                 fs::StaticFiles::new("./static/")
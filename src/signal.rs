@@ -0,0 +1,168 @@
+use radiotap::field::Kind;
+use radiotap::RadiotapIterator;
+use serde::{Serialize, Deserialize};
+
+// Weight of a new sample in the moving average
+const SIGNAL_SMOOTHING: f32 = 0.2;
+
+// The signal of one receive chain of the capturing adapter
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AntennaSignal {
+    pub antenna: u8,
+    pub signal: i8, // dBm
+}
+
+// Signals of a radiotap header. Adapters with several receive chains add one
+// radiotap namespace per antenna after the combined signal.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RadiotapSignals {
+    pub combined: Option<i8>, // dBm
+    pub antennas: Vec<AntennaSignal>,
+}
+
+impl RadiotapSignals {
+    // `Radiotap` keeps the last value of a repeated field, the per antenna signal
+    // instead of the combined one
+    pub fn from_bytes(input: &[u8]) -> RadiotapSignals {
+        let mut signals = RadiotapSignals::default();
+        let iterator = match RadiotapIterator::from_bytes(input) {
+            Ok(iterator) => iterator,
+            Err(_) => return signals,
+        };
+
+        // An antenna signal followed by an antenna index belongs to that antenna
+        let mut pending = None;
+        for field in &iterator {
+            let (kind, data) = match field {
+                Ok(field) => field,
+                Err(_) => break,
+            };
+            match (kind, data.first()) {
+                (Kind::AntennaSignal, Some(value)) => {
+                    if let Some(signal) = pending.replace(*value as i8) {
+                        signals.combined.get_or_insert(signal);
+                    }
+                }
+                (Kind::Antenna, Some(antenna)) => {
+                    if let Some(signal) = pending.take() {
+                        signals.antennas.push(AntennaSignal {
+                            antenna: *antenna,
+                            signal,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(signal) = pending {
+            signals.combined.get_or_insert(signal);
+        }
+        // A single antenna is also the combined signal
+        if signals.combined.is_none() && signals.antennas.len() == 1 {
+            signals.combined = Some(signals.antennas[0].signal);
+        }
+        signals
+    }
+}
+
+// Signal classes of the graph nodes, from the smoothed signal
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SignalQuality {
+    #[default]
+    Unknown, // no sample yet
+    Excellent,
+    Good,
+    Fair,
+    Weak,
+    Poor,
+}
+
+impl SignalQuality {
+    pub fn from_signal(signal: f32) -> SignalQuality {
+        match signal {
+            s if s >= -50.0 => SignalQuality::Excellent,
+            s if s >= -60.0 => SignalQuality::Good,
+            s if s >= -70.0 => SignalQuality::Fair,
+            s if s >= -80.0 => SignalQuality::Weak,
+            _ => SignalQuality::Poor,
+        }
+    }
+}
+
+// RSSI samples of a device, in dBm
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq)]
+pub struct Rssi {
+    pub min: i8,
+    pub max: i8,
+    pub mean: f32,
+    pub ewma: f32, // exponentially weighted, follows the device when it moves
+    pub samples: u64,
+}
+
+impl Rssi {
+    pub fn record(&mut self, signal: i8) {
+        let value = f32::from(signal);
+        if self.samples == 0 {
+            self.min = signal;
+            self.max = signal;
+            self.mean = value;
+            self.ewma = value;
+        } else {
+            self.min = self.min.min(signal);
+            self.max = self.max.max(signal);
+            self.mean += (value - self.mean) / (self.samples + 1) as f32;
+            self.ewma += SIGNAL_SMOOTHING * (value - self.ewma);
+        }
+        self.samples += 1;
+    }
+
+    // The smoothed signal, 0 without samples like the unknown signal of a frame
+    pub fn signal(&self) -> i8 {
+        self.ewma.round() as i8
+    }
+
+    pub fn quality(&self) -> SignalQuality {
+        if self.samples == 0 {
+            return SignalQuality::Unknown;
+        }
+        SignalQuality::from_signal(self.ewma)
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub struct AntennaRssi {
+    pub antenna: u8,
+    #[serde(flatten)]
+    pub rssi: Rssi,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SignalStats {
+    #[serde(flatten)]
+    pub rssi: Rssi,
+    pub antennas: Vec<AntennaRssi>,
+}
+
+impl SignalStats {
+    pub fn record(&mut self, signal: i8, antennas: &[AntennaSignal]) {
+        self.rssi.record(signal);
+
+        for sample in antennas {
+            match self
+                .antennas
+                .iter_mut()
+                .find(|antenna| antenna.antenna == sample.antenna)
+            {
+                Some(antenna) => antenna.rssi.record(sample.signal),
+                None => {
+                    let mut rssi = Rssi::default();
+                    rssi.record(sample.signal);
+                    self.antennas.push(AntennaRssi {
+                        antenna: sample.antenna,
+                        rssi,
+                    });
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use crate::errors::*;
+use crate::pcap_writer::PcapWriter;
+use pcap::{Activated, Active, Capture, Linktype, Offline};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::Duration;
+
+const TIMEOUT: i32 = 10;
+pub const DLT_IEEE802_11_RADIO: i32 = 127;
+
+// Result of asking a PacketSource for its next frame
+#[derive(Clone, Debug, PartialEq)]
+pub enum Next<'a> {
+    Frame(&'a [u8], Duration), // radiotap frame and its capture time since the Unix epoch
+    Timeout,                   // nothing arrived yet, ask again
+    Done,                      // the source is exhausted
+}
+
+pub trait PacketSource {
+    fn next_frame(&mut self) -> Result<Next<'_>>;
+}
+
+// Any libpcap handle delivering radiotap frames: a live device, a capture file or stdin
+pub struct PcapSource<T: Activated + ?Sized> {
+    capture: Capture<T>,
+    writer: Option<PcapWriter>,
+}
+
+impl PcapSource<Active> {
+    pub fn live(device: &str) -> Result<PcapSource<Active>> {
+        let capture = Capture::from_device(device)?;
+        let mut capture = match capture.timeout(TIMEOUT).rfmon(true).open() {
+            Ok(capture) => capture,
+            Err(_e) => bail!("Can not open device, you need root access"),
+        };
+
+        if capture
+            .set_datalink(Linktype(DLT_IEEE802_11_RADIO))
+            .is_err()
+        {
+            bail!("Can not set datalink")
+        }
+
+        Ok(PcapSource {
+            capture,
+            writer: None,
+        })
+    }
+}
+
+impl PcapSource<Offline> {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<PcapSource<Offline>> {
+        PcapSource::offline(Capture::from_file(path)?)
+    }
+
+    // Reads a pcap stream from stdin, i.e. `tcpdump -w - | nearby --read -`
+    pub fn from_stdin() -> Result<PcapSource<Offline>> {
+        PcapSource::offline(Capture::from_raw_fd(0)?)
+    }
+
+    fn offline(capture: Capture<Offline>) -> Result<PcapSource<Offline>> {
+        if capture.get_datalink() != Linktype(DLT_IEEE802_11_RADIO) {
+            bail!("Capture does not contain radiotap frames")
+        }
+
+        Ok(PcapSource {
+            capture,
+            writer: None,
+        })
+    }
+}
+
+impl<T: Activated + ?Sized> PcapSource<T> {
+    // Every frame read from this source is also saved by the writer
+    pub fn set_writer(&mut self, writer: PcapWriter) {
+        self.writer = Some(writer);
+    }
+}
+
+impl<T: Activated + ?Sized> PacketSource for PcapSource<T> {
+    fn next_frame(&mut self) -> Result<Next<'_>> {
+        match self.capture.next() {
+            Ok(packet) => {
+                if let Some(ref mut writer) = self.writer {
+                    writer.write(&packet)?;
+                }
+                let ts = packet.header.ts;
+                let timestamp = Duration::new(ts.tv_sec as u64, ts.tv_usec as u32 * 1000);
+                Ok(Next::Frame(packet.data, timestamp))
+            }
+            // There were no packets on the interface before the timeout
+            Err(pcap::Error::TimeoutExpired) => Ok(Next::Timeout),
+            // Reached the end of the capture file
+            Err(pcap::Error::NoMorePackets) => Ok(Next::Done),
+            Err(e) => bail!("Unexpect error: {}", e.to_string()),
+        }
+    }
+}
+
+// Frames kept in memory with their capture time since the Unix epoch, useful to
+// replay synthetic frames
+#[derive(Clone, Debug, Default)]
+pub struct MemorySource {
+    frames: VecDeque<(Vec<u8>, Duration)>,
+    current: Vec<u8>,
+}
+
+impl MemorySource {
+    pub fn new(frames: Vec<(Vec<u8>, Duration)>) -> MemorySource {
+        MemorySource {
+            frames: frames.into(),
+            current: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, frame: Vec<u8>, timestamp: Duration) {
+        self.frames.push_back((frame, timestamp));
+    }
+}
+
+impl PacketSource for MemorySource {
+    fn next_frame(&mut self) -> Result<Next<'_>> {
+        match self.frames.pop_front() {
+            Some((frame, timestamp)) => {
+                self.current = frame;
+                Ok(Next::Frame(&self.current, timestamp))
+            }
+            None => Ok(Next::Done),
+        }
+    }
+}
@@ -4,6 +4,7 @@ use crate::mapper::*;
 use crate::errors::*;
 use serde_json::to_string_pretty;
 use serde::{Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[derive(Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -29,6 +30,13 @@ pub fn flag_is_set(data: u8, bit: u8) -> bool {
     }
 }
 
+// Current time since the Unix epoch, the timestamp of frames that weren't captured
+pub fn unix_time() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
 pub fn create_netjson(mapper: Mapper) -> Result<String> {
     // Print Access Point information
     let mut net: Vec<Collection> = Vec::new();
@@ -0,0 +1,191 @@
+use crate::dot11::header::Dot11Header;
+use crate::dot11::info::{Beacon, BodyInformation};
+use crate::dot11::elements::InformationElement;
+use crate::dot11::mac::MacAddr;
+use crate::dot11::writer::{Encode, Writer};
+use crate::wids::*;
+use radiotap::Radiotap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+// Traffic indication map, changes with every beacon
+const TIM_ELEMENT: u8 = 5;
+
+#[derive(Copy, Clone, Debug)]
+pub struct BeaconFloodConfig {
+    pub window: Duration,
+    pub threshold: usize,          // new BSSIDs inside the window
+    pub template_threshold: usize, // new BSSIDs sending the same beacon template
+    pub quarantine: Duration,      // silence before a BSSID is forgotten
+}
+
+impl Default for BeaconFloodConfig {
+    fn default() -> BeaconFloodConfig {
+        BeaconFloodConfig {
+            window: Duration::from_secs(10),
+            threshold: 100,
+            template_threshold: 20,
+            quarantine: Duration::from_secs(600),
+        }
+    }
+}
+
+// A BSSID seen for the first time
+#[derive(Copy, Clone, Debug)]
+struct NewBssid {
+    timestamp: Duration,
+    bssid: MacAddr,
+    template: u64,
+}
+
+// Tools like mdk4 send beacons for thousands of made up access points. They show up
+// as a burst of new BSSIDs, often with random MACs and copies of one beacon.
+#[derive(Clone, Debug, Default)]
+pub struct BeaconFloodDetector {
+    pub config: BeaconFloodConfig,
+    seen: HashMap<MacAddr, Duration>, // last beacon of every BSSID
+    recent: VecDeque<NewBssid>,
+    templates: HashMap<u64, usize>, // new BSSIDs per template inside the window
+    quarantined: HashMap<MacAddr, Duration>, // fake access points and their last beacon
+    newly_quarantined: Vec<MacAddr>,
+    alerted: Option<Duration>,
+    last_sweep: Duration,
+}
+
+impl BeaconFloodDetector {
+    pub fn new(config: BeaconFloodConfig) -> BeaconFloodDetector {
+        BeaconFloodDetector {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_quarantined(&self, bssid: MacAddr) -> bool {
+        self.quarantined.contains_key(&bssid)
+    }
+
+    // BSSIDs quarantined since the last call
+    pub fn take_quarantined(&mut self) -> Vec<MacAddr> {
+        std::mem::take(&mut self.newly_quarantined)
+    }
+
+    // Everything but the SSID, the channel and the TIM, which the tools vary
+    fn template(beacon: &Beacon) -> u64 {
+        let mut writer = Writer::new();
+        writer.u16(beacon.interval);
+        writer.u16(beacon.cap_info);
+        for element in &beacon.elements {
+            match element {
+                InformationElement::Ssid(_) | InformationElement::DsParameterSet(_) => {}
+                InformationElement::Unknown { id, .. } if *id == TIM_ELEMENT => {}
+                _ => element.encode(&mut writer),
+            }
+        }
+
+        let mut hasher = DefaultHasher::new();
+        writer.into_bytes().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn add(&mut self, new: NewBssid) {
+        *self.templates.entry(new.template).or_default() += 1;
+        self.recent.push_back(new);
+
+        let window = self.config.window;
+        while let Some(first) = self.recent.front() {
+            if new.timestamp.saturating_sub(first.timestamp) < window {
+                break;
+            }
+            if let Some(count) = self.templates.get_mut(&first.template) {
+                *count -= 1;
+                if *count == 0 {
+                    self.templates.remove(&first.template);
+                }
+            }
+            self.recent.pop_front();
+        }
+    }
+
+    // While flooding, the new BSSIDs with a random MAC or a repeated template are fake
+    fn quarantine(&mut self, timestamp: Duration) -> Option<AlertKind> {
+        let largest_template = self.templates.values().copied().max().unwrap_or_default();
+        if self.recent.len() < self.config.threshold
+            && largest_template < self.config.template_threshold
+        {
+            return None;
+        }
+
+        for new in &self.recent {
+            let fake = new.bssid.is_locally_administered()
+                || self.templates[&new.template] >= self.config.template_threshold;
+            if fake && !self.quarantined.contains_key(&new.bssid) {
+                self.quarantined.insert(new.bssid, timestamp);
+                self.newly_quarantined.push(new.bssid);
+            }
+        }
+
+        match self.alerted {
+            Some(last) if timestamp.saturating_sub(last) < self.config.window => None,
+            _ => {
+                self.alerted = Some(timestamp);
+                Some(AlertKind::BeaconFlood(BeaconFlood {
+                    new_bssids: self.recent.len(),
+                    random_macs: self
+                        .recent
+                        .iter()
+                        .filter(|new| new.bssid.is_locally_administered())
+                        .count(),
+                    largest_template,
+                    quarantined: self.quarantined.len(),
+                    window: self.config.window.as_secs(),
+                }))
+            }
+        }
+    }
+
+    // Forgets the access points that stopped beaconing, fake ones included
+    fn sweep(&mut self, timestamp: Duration) {
+        if timestamp.saturating_sub(self.last_sweep) < self.config.window {
+            return;
+        }
+        let quarantine = self.config.quarantine;
+        self.seen
+            .retain(|_, last| timestamp.saturating_sub(*last) < quarantine);
+        self.quarantined
+            .retain(|_, last| timestamp.saturating_sub(*last) < quarantine);
+        self.last_sweep = timestamp;
+    }
+}
+
+impl Detector for BeaconFloodDetector {
+    fn inspect(
+        &mut self, _radiotap: &Radiotap, header: &Dot11Header, timestamp: Duration,
+    ) -> Vec<AlertKind> {
+        self.sweep(timestamp);
+
+        let beacon = match header.info {
+            BodyInformation::Beacon(ref beacon) => beacon,
+            _ => return Vec::new(),
+        };
+        let bssid = header.bssid;
+        if bssid.is_broadcast() || bssid.is_unspecified() {
+            return Vec::new();
+        }
+
+        if let Some(last) = self.quarantined.get_mut(&bssid) {
+            *last = timestamp;
+        }
+        if self.seen.insert(bssid, timestamp).is_some() {
+            return Vec::new();
+        }
+
+        self.add(NewBssid {
+            timestamp,
+            bssid,
+            template: BeaconFloodDetector::template(beacon),
+        });
+        self.quarantine(timestamp).into_iter().collect()
+    }
+}
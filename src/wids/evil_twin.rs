@@ -0,0 +1,220 @@
+use crate::dot11::header::Dot11Header;
+use crate::dot11::info::BodyInformation;
+use crate::dot11::mac::{MacAddr, Oui};
+use crate::dot11::security::Security;
+use crate::signal::SignalStats;
+use crate::wids::*;
+use radiotap::Radiotap;
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+const OPEN: &str = "Open";
+// Beacons needed before the average signal is trusted
+const SIGNAL_SAMPLES: u64 = 5;
+
+// What an access point advertising an authorized SSID may look like, empty lists
+// allow anything
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct AuthorizedNetwork {
+    pub ssid: String,
+    pub bssids: Vec<MacAddr>,
+    pub ouis: Vec<Oui>, // "00:11:22"
+    pub channels: Vec<u8>,
+    pub security: Vec<String>, // Security labels: "WPA2-PSK", "WPA3-SAE", ...
+}
+
+impl AuthorizedNetwork {
+    // An access point is known by its BSSID or by the OUI of its vendor
+    pub fn allows_bssid(&self, bssid: MacAddr) -> bool {
+        if self.bssids.is_empty() && self.ouis.is_empty() {
+            return true;
+        }
+        self.bssids.contains(&bssid) || self.ouis.contains(&Oui::from(bssid))
+    }
+
+    pub fn allows_channel(&self, channel: u8) -> bool {
+        // Channel 0: the frame didn't carry a DS Parameter Set
+        self.channels.is_empty() || channel == 0 || self.channels.contains(&channel)
+    }
+
+    pub fn allows_security(&self, label: &str) -> bool {
+        self.security.is_empty() || self.security.iter().any(|allowed| allowed == label)
+    }
+
+    pub fn is_protected(&self) -> bool {
+        !self.security.is_empty() && !self.allows_security(OPEN)
+    }
+}
+
+// The authorized networks, loaded from a JSON array of AuthorizedNetwork
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Baseline {
+    pub networks: HashMap<String, AuthorizedNetwork>, // by SSID
+}
+
+impl Baseline {
+    pub fn new(networks: Vec<AuthorizedNetwork>) -> Baseline {
+        let networks = networks
+            .into_iter()
+            .map(|network| (network.ssid.clone(), network))
+            .collect();
+
+        Baseline { networks }
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Baseline> {
+        let content = fs::read_to_string(path)?;
+        let networks: Vec<AuthorizedNetwork> = serde_json::from_str(&content)?;
+
+        Ok(Baseline::new(networks))
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct EvilTwinConfig {
+    pub signal_jump: u8, // dB away from the average signal of the access point
+    pub signal_window: Duration, // between two signal jump alerts of one access point
+}
+
+impl Default for EvilTwinConfig {
+    fn default() -> EvilTwinConfig {
+        EvilTwinConfig {
+            signal_jump: 20,
+            signal_window: Duration::from_secs(60),
+        }
+    }
+}
+
+// Compares the beacons and probe responses with the baseline. Without one, it still
+// reports open copies of SSIDs seen protected.
+#[derive(Clone, Debug, Default)]
+pub struct EvilTwinDetector {
+    pub baseline: Baseline,
+    pub config: EvilTwinConfig,
+    protected: HashMap<String, MacAddr>, // SSID and the first protected BSSID
+    signals: HashMap<MacAddr, SignalStats>,
+    signal_alerts: HashMap<MacAddr, Duration>,
+    alerted: HashSet<(MacAddr, String, Deviation)>,
+}
+
+impl EvilTwinDetector {
+    pub fn new(baseline: Baseline) -> EvilTwinDetector {
+        EvilTwinDetector {
+            baseline,
+            ..Default::default()
+        }
+    }
+
+    fn deviations(
+        &mut self, ssid: &str, bssid: MacAddr, channel: u8, security: &Security,
+    ) -> Vec<Deviation> {
+        let mut deviations = Vec::new();
+        let open = !security.privacy;
+
+        if !open {
+            self.protected.entry(ssid.to_string()).or_insert(bssid);
+        }
+        let seen_protected = self
+            .protected
+            .get(ssid)
+            .is_some_and(|first| *first != bssid);
+
+        let network = self.baseline.networks.get(ssid);
+        if open && (seen_protected || network.is_some_and(|network| network.is_protected())) {
+            deviations.push(Deviation::OpenCopy);
+        }
+        if let Some(network) = network {
+            if !network.allows_bssid(bssid) {
+                deviations.push(Deviation::UnknownBssid);
+            }
+            if !network.allows_channel(channel) {
+                deviations.push(Deviation::UnexpectedChannel(channel));
+            }
+            if !open && !network.allows_security(&security.label) {
+                deviations.push(Deviation::UnexpectedSecurity(security.label.clone()));
+            }
+        }
+        deviations
+    }
+
+    // A spoofed BSSID transmitted from somewhere else shows up as a sudden change of
+    // the signal of an authorized access point
+    fn signal_jump(
+        &mut self, bssid: MacAddr, signal: i8, timestamp: Duration,
+    ) -> Option<Deviation> {
+        let stats = self.signals.entry(bssid).or_default();
+        let previous = stats.rssi.ewma;
+        let jump = stats.rssi.samples >= SIGNAL_SAMPLES
+            && (f32::from(signal) - previous).abs() >= f32::from(self.config.signal_jump);
+        stats.record(signal, &[]);
+
+        if !jump {
+            return None;
+        }
+        match self.signal_alerts.get(&bssid) {
+            Some(last) if timestamp.saturating_sub(*last) < self.config.signal_window => None,
+            _ => {
+                self.signal_alerts.insert(bssid, timestamp);
+                Some(Deviation::SignalJump {
+                    average: previous.round() as i8,
+                    signal,
+                })
+            }
+        }
+    }
+}
+
+impl Detector for EvilTwinDetector {
+    fn inspect(
+        &mut self, radiotap: &Radiotap, header: &Dot11Header, timestamp: Duration,
+    ) -> Vec<AlertKind> {
+        let (ssid, channel, security) = match header.info {
+            BodyInformation::Beacon(ref beacon) => {
+                (&beacon.ssid.value, beacon.current_channel, &beacon.security)
+            }
+            BodyInformation::ProbeResponse(ref probe) => {
+                (&probe.ssid.value, probe.current_channel, &probe.security)
+            }
+            _ => return Vec::new(),
+        };
+        if ssid.is_empty() {
+            return Vec::new();
+        }
+
+        let bssid = header.bssid;
+        let mut deviations = self.deviations(ssid, bssid, channel, security);
+        // Beacons only, probe responses are sent at a higher rate
+        if let (BodyInformation::Beacon(_), Some(signal)) = (&header.info, radiotap.antenna_signal)
+        {
+            let authorized = self
+                .baseline
+                .networks
+                .get(ssid)
+                .is_some_and(|network| network.allows_bssid(bssid));
+            if authorized {
+                deviations.extend(self.signal_jump(bssid, signal.value, timestamp));
+            }
+        }
+
+        deviations
+            .into_iter()
+            .filter(|deviation| match deviation {
+                Deviation::SignalJump { .. } => true,
+                _ => self
+                    .alerted
+                    .insert((bssid, ssid.clone(), deviation.clone())),
+            })
+            .map(|deviation| {
+                AlertKind::RogueAccessPoint(RogueAccessPoint {
+                    ssid: ssid.clone(),
+                    bssid,
+                    deviation,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,142 @@
+use crate::dot11::info::BodyInformation;
+use crate::dot11::header::Dot11Header;
+use crate::dot11::mac::MacAddr;
+use crate::wids::*;
+use radiotap::Radiotap;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Disconnection {
+    Deauthentication,
+    Disassociation,
+}
+
+// (subtype, BSSID, client), no client for the count over the whole BSS
+type FloodKey = (Disconnection, MacAddr, Option<MacAddr>);
+
+#[derive(Copy, Clone, Debug)]
+pub struct FloodConfig {
+    pub window: Duration,
+    pub threshold: usize,           // frames to or from one client
+    pub broadcast_threshold: usize, // broadcast frames, rarely sent by a healthy AP
+    pub bssid_threshold: usize,     // frames to any client of the access point
+}
+
+impl Default for FloodConfig {
+    fn default() -> FloodConfig {
+        FloodConfig {
+            window: Duration::from_secs(10),
+            threshold: 10,
+            broadcast_threshold: 5,
+            bssid_threshold: 30,
+        }
+    }
+}
+
+// Counts the unprotected deauthentication and disassociation frames over a sliding
+// window. Protected frames (802.11w) can't be forged, so they aren't counted.
+#[derive(Clone, Debug, Default)]
+pub struct FloodDetector {
+    pub config: FloodConfig,
+    frames: HashMap<FloodKey, VecDeque<Duration>>,
+    alerted: HashMap<FloodKey, Duration>,
+    last_sweep: Duration,
+}
+
+impl FloodDetector {
+    pub fn new(config: FloodConfig) -> FloodDetector {
+        FloodDetector {
+            config,
+            ..Default::default()
+        }
+    }
+
+    // Number of frames inside the window, once it crosses the threshold the key
+    // doesn't alert again for a whole window
+    fn count(&mut self, key: FloodKey, threshold: usize, timestamp: Duration) -> Option<usize> {
+        let window = self.config.window;
+        let frames = self.frames.entry(key).or_default();
+        frames.push_back(timestamp);
+        while frames
+            .front()
+            .is_some_and(|first| timestamp.saturating_sub(*first) >= window)
+        {
+            frames.pop_front();
+        }
+
+        let count = frames.len();
+        if count < threshold {
+            return None;
+        }
+        match self.alerted.get(&key) {
+            Some(last) if timestamp.saturating_sub(*last) < window => None,
+            _ => {
+                self.alerted.insert(key, timestamp);
+                Some(count)
+            }
+        }
+    }
+
+    // Forgets the stations that went quiet
+    fn sweep(&mut self, timestamp: Duration) {
+        if timestamp.saturating_sub(self.last_sweep) < self.config.window {
+            return;
+        }
+        let window = self.config.window;
+        self.frames.retain(|_, frames| {
+            frames
+                .back()
+                .is_some_and(|last| timestamp.saturating_sub(*last) < window)
+        });
+        self.alerted
+            .retain(|_, last| timestamp.saturating_sub(*last) < window);
+        self.last_sweep = timestamp;
+    }
+}
+
+impl Detector for FloodDetector {
+    fn inspect(
+        &mut self, _radiotap: &Radiotap, header: &Dot11Header, timestamp: Duration,
+    ) -> Vec<AlertKind> {
+        self.sweep(timestamp);
+
+        let subtype = match header.info {
+            BodyInformation::Deauthentication(_) => Disconnection::Deauthentication,
+            BodyInformation::Disassociation(_) => Disconnection::Disassociation,
+            _ => return Vec::new(),
+        };
+        // Either end may send it, the other one is the client
+        let client = if header.transmitter == header.bssid {
+            header.receiver
+        } else {
+            header.transmitter
+        };
+        let client_threshold = if client.is_broadcast() {
+            self.config.broadcast_threshold
+        } else {
+            self.config.threshold
+        };
+
+        let keys = [
+            ((subtype, header.bssid, Some(client)), client_threshold),
+            ((subtype, header.bssid, None), self.config.bssid_threshold),
+        ];
+        let mut alerts = Vec::new();
+        for (key, threshold) in keys.iter() {
+            if let Some(frames) = self.count(*key, *threshold, timestamp) {
+                let flood = Flood {
+                    bssid: header.bssid,
+                    client: key.2,
+                    frames,
+                    window: self.config.window.as_secs(),
+                };
+                alerts.push(match subtype {
+                    Disconnection::Deauthentication => AlertKind::DeauthenticationFlood(flood),
+                    Disconnection::Disassociation => AlertKind::DisassociationFlood(flood),
+                });
+            }
+        }
+        alerts
+    }
+}
@@ -0,0 +1,107 @@
+use crate::dot11::header::Dot11Header;
+use crate::dot11::info::BodyInformation;
+use crate::dot11::mac::MacAddr;
+use crate::wids::*;
+use radiotap::Radiotap;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Copy, Clone, Debug)]
+pub struct KarmaConfig {
+    pub window: Duration,
+    pub threshold: usize, // probed SSIDs answered by one BSSID
+}
+
+impl Default for KarmaConfig {
+    fn default() -> KarmaConfig {
+        KarmaConfig {
+            window: Duration::from_secs(300),
+            threshold: 3,
+        }
+    }
+}
+
+// KARMA and MANA access points answer the directed probe requests of the clients
+// with whatever SSID they asked for. A genuine access point only answers for its own.
+#[derive(Clone, Debug, Default)]
+pub struct KarmaDetector {
+    pub config: KarmaConfig,
+    probed: HashMap<String, Duration>, // SSIDs of the directed probe requests
+    answered: HashMap<MacAddr, HashMap<String, Duration>>, // probed SSIDs answered per BSSID
+    alerted: HashMap<MacAddr, Duration>,
+    last_sweep: Duration,
+}
+
+impl KarmaDetector {
+    pub fn new(config: KarmaConfig) -> KarmaDetector {
+        KarmaDetector {
+            config,
+            ..Default::default()
+        }
+    }
+
+    fn recent(&self, seen: Duration, timestamp: Duration) -> bool {
+        timestamp.saturating_sub(seen) < self.config.window
+    }
+
+    fn answer(&mut self, bssid: MacAddr, ssid: &str, timestamp: Duration) -> Option<AlertKind> {
+        match self.probed.get(ssid) {
+            Some(probed) if self.recent(*probed, timestamp) => {}
+            _ => return None,
+        }
+
+        let window = self.config.window;
+        let answered = self.answered.entry(bssid).or_default();
+        answered.insert(ssid.to_string(), timestamp);
+        answered.retain(|_, seen| timestamp.saturating_sub(*seen) < window);
+        if answered.len() < self.config.threshold {
+            return None;
+        }
+
+        let mut ssids: Vec<String> = answered.keys().cloned().collect();
+        ssids.sort();
+        match self.alerted.get(&bssid) {
+            Some(last) if self.recent(*last, timestamp) => None,
+            _ => {
+                self.alerted.insert(bssid, timestamp);
+                Some(AlertKind::Karma(Karma { bssid, ssids }))
+            }
+        }
+    }
+
+    fn sweep(&mut self, timestamp: Duration) {
+        if timestamp.saturating_sub(self.last_sweep) < self.config.window {
+            return;
+        }
+        let window = self.config.window;
+        let recent = |seen: &Duration| timestamp.saturating_sub(*seen) < window;
+        self.probed.retain(|_, seen| recent(seen));
+        self.answered.retain(|_, answered| {
+            answered.retain(|_, seen| recent(seen));
+            !answered.is_empty()
+        });
+        self.alerted.retain(|_, seen| recent(seen));
+        self.last_sweep = timestamp;
+    }
+}
+
+impl Detector for KarmaDetector {
+    fn inspect(
+        &mut self, _radiotap: &Radiotap, header: &Dot11Header, timestamp: Duration,
+    ) -> Vec<AlertKind> {
+        self.sweep(timestamp);
+
+        match header.info {
+            // Broadcast probe requests, with an empty SSID, don't ask for a network
+            BodyInformation::ProbeRequest(ref probe) if !probe.ssid.value.is_empty() => {
+                self.probed.insert(probe.ssid.value.clone(), timestamp);
+                Vec::new()
+            }
+            BodyInformation::ProbeResponse(ref probe) if !probe.ssid.value.is_empty() => self
+                .answer(header.bssid, &probe.ssid.value, timestamp)
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
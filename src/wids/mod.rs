@@ -0,0 +1,262 @@
+use crate::errors::*;
+use crate::dot11::header::Dot11Header;
+use crate::dot11::mac::MacAddr;
+use radiotap::Radiotap;
+use serde::{Serialize, Deserialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::prelude::*;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub mod beacon_flood;
+pub mod evil_twin;
+pub mod flood;
+pub mod karma;
+
+use self::beacon_flood::*;
+use self::evil_twin::*;
+use self::flood::*;
+use self::karma::*;
+
+// Something suspicious seen on the air
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Alert {
+    pub timestamp: u64, // seconds since the Unix epoch
+    pub kind: AlertKind,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum AlertKind {
+    DeauthenticationFlood(Flood),
+    DisassociationFlood(Flood),
+    RogueAccessPoint(RogueAccessPoint),
+    Karma(Karma),
+    BeaconFlood(BeaconFlood),
+}
+
+// Frames sent to disconnect the clients of an access point
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Flood {
+    pub bssid: MacAddr,
+    pub client: Option<MacAddr>, // None when counted over every client of the BSSID
+    pub frames: usize,
+    pub window: u64, // seconds
+}
+
+// Beacons of many made up access points
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BeaconFlood {
+    pub new_bssids: usize,
+    pub random_macs: usize, // new BSSIDs with a locally administered address
+    pub largest_template: usize, // new BSSIDs sending the same beacon
+    pub quarantined: usize, // fake access points kept out of the network map
+    pub window: u64,        // seconds
+}
+
+// A beacon or probe response that doesn't match the authorized networks
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RogueAccessPoint {
+    pub ssid: String,
+    pub bssid: MacAddr,
+    pub deviation: Deviation,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Deviation {
+    UnknownBssid,
+    UnexpectedChannel(u8),
+    UnexpectedSecurity(String),
+    OpenCopy, // an open network with the SSID of a protected one
+    SignalJump { average: i8, signal: i8 },
+}
+
+// An access point answering the probe requests for several SSIDs (KARMA/MANA)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Karma {
+    pub bssid: MacAddr,
+    pub ssids: Vec<String>, // probed SSIDs it answered, sorted
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (name, flood) = match self.kind {
+            AlertKind::DeauthenticationFlood(ref flood) => ("Deauthentication", flood),
+            AlertKind::DisassociationFlood(ref flood) => ("Disassociation", flood),
+            AlertKind::RogueAccessPoint(ref rogue) => return rogue.fmt(f),
+            AlertKind::Karma(ref karma) => return karma.fmt(f),
+            AlertKind::BeaconFlood(ref flood) => return flood.fmt(f),
+        };
+        let target = match flood.client {
+            Some(client) if client.is_broadcast() => "every client (broadcast)".to_string(),
+            Some(client) => format!("client {}", client),
+            None => "several clients".to_string(),
+        };
+        write!(
+            f,
+            "{} flood: {} frames in {} sec to {} of {}",
+            name, flood.frames, flood.window, target, flood.bssid
+        )
+    }
+}
+
+impl fmt::Display for RogueAccessPoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let deviation = match self.deviation {
+            Deviation::UnknownBssid => "unknown BSSID".to_string(),
+            Deviation::UnexpectedChannel(channel) => format!("unexpected channel {}", channel),
+            Deviation::UnexpectedSecurity(ref label) => format!("unexpected security {}", label),
+            Deviation::OpenCopy => "open copy of a protected network".to_string(),
+            Deviation::SignalJump { average, signal } => {
+                format!("signal jumped from {} to {} dBm", average, signal)
+            }
+        };
+        write!(
+            f,
+            "Rogue access point {} ({}): {}",
+            self.ssid, self.bssid, deviation
+        )
+    }
+}
+
+impl fmt::Display for BeaconFlood {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Beacon flood: {} new BSSIDs in {} sec ({} random MACs, {} with the same beacon), \
+             {} fake access points quarantined",
+            self.new_bssids, self.window, self.random_macs, self.largest_template, self.quarantined
+        )
+    }
+}
+
+impl fmt::Display for Karma {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Karma access point {} answered probes for {} SSIDs: {}",
+            self.bssid,
+            self.ssids.len(),
+            self.ssids.join(", ")
+        )
+    }
+}
+
+// A detection rule fed with every parsed frame
+pub trait Detector {
+    fn inspect(
+        &mut self, radiotap: &Radiotap, header: &Dot11Header, timestamp: Duration,
+    ) -> Vec<AlertKind>;
+}
+
+// Wireless intrusion detection, runs the detectors over the frames of a scan
+#[derive(Clone, Debug, Default)]
+pub struct Wids {
+    pub flood: FloodDetector,
+    pub evil_twin: EvilTwinDetector,
+    pub karma: KarmaDetector,
+    pub beacon_flood: BeaconFloodDetector,
+    pending: Vec<Alert>,
+}
+
+impl Wids {
+    pub fn new() -> Wids {
+        Wids::default()
+    }
+
+    pub fn inspect(&mut self, radiotap: &Radiotap, header: &Dot11Header, timestamp: Duration) {
+        let detectors: [&mut dyn Detector; 4] = [
+            &mut self.flood,
+            &mut self.evil_twin,
+            &mut self.karma,
+            &mut self.beacon_flood,
+        ];
+
+        for detector in detectors {
+            for kind in detector.inspect(radiotap, header, timestamp) {
+                self.pending.push(Alert {
+                    timestamp: timestamp.as_secs(),
+                    kind,
+                });
+            }
+        }
+    }
+
+    // Fake access points of a beacon flood, their frames are left out of the network map
+    pub fn is_quarantined(&self, bssid: MacAddr) -> bool {
+        self.beacon_flood.is_quarantined(bssid)
+    }
+
+    // BSSIDs quarantined since the last call
+    pub fn take_quarantined(&mut self) -> Vec<MacAddr> {
+        self.beacon_flood.take_quarantined()
+    }
+
+    // Alerts raised since the last call
+    pub fn take_alerts(&mut self) -> Vec<Alert> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+// Where the alerts are delivered
+pub trait AlertSink {
+    fn emit(&mut self, alert: &Alert) -> Result<()>;
+}
+
+// One JSON document per line, appended to the file
+pub struct JsonLinesSink {
+    file: File,
+}
+
+impl JsonLinesSink {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<JsonLinesSink> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(JsonLinesSink { file })
+    }
+}
+
+impl AlertSink for JsonLinesSink {
+    fn emit(&mut self, alert: &Alert) -> Result<()> {
+        let line = serde_json::to_string(alert)?;
+        writeln!(self.file, "{}", line)?;
+        Ok(())
+    }
+}
+
+// The most recent alerts, shared with the web server
+#[derive(Clone, Debug)]
+pub struct SharedAlerts {
+    alerts: Arc<Mutex<VecDeque<Alert>>>,
+    capacity: usize,
+}
+
+impl SharedAlerts {
+    pub fn new(capacity: usize) -> SharedAlerts {
+        SharedAlerts {
+            alerts: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<Alert> {
+        match self.alerts.lock() {
+            Ok(alerts) => alerts.iter().cloned().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+impl AlertSink for SharedAlerts {
+    fn emit(&mut self, alert: &Alert) -> Result<()> {
+        if let Ok(mut alerts) = self.alerts.lock() {
+            if alerts.len() == self.capacity {
+                alerts.pop_front();
+            }
+            alerts.push_back(alert.clone());
+        }
+        Ok(())
+    }
+}
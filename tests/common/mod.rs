@@ -0,0 +1,114 @@
+// Builds raw radiotap + 802.11 frames for the parser tests
+#![allow(dead_code)]
+
+pub const BROADCAST: [u8; 6] = [0xff; 6];
+
+// Minimal radiotap header: version 0, length 8, no fields present
+pub fn radiotap() -> Vec<u8> {
+    vec![0, 0, 8, 0, 0, 0, 0, 0]
+}
+
+pub fn frame_control(frame_type: u8, subtype: u8, flags: u8) -> [u8; 2] {
+    [(subtype << 4) | (frame_type << 2), flags]
+}
+
+// LLC/SNAP + EAPOL-Key with a 16 bytes MIC, nonce and MIC filled with the given bytes
+pub fn eapol_key(
+    key_info: u16, replay_counter: u64, nonce: u8, mic: u8, key_data: &[u8],
+) -> Vec<u8> {
+    let mut key = vec![2]; // RSN descriptor
+    key.extend_from_slice(&key_info.to_be_bytes());
+    key.extend_from_slice(&16u16.to_be_bytes());
+    key.extend_from_slice(&replay_counter.to_be_bytes());
+    key.extend_from_slice(&[nonce; 32]);
+    key.extend_from_slice(&[0; 16 + 8 + 8]); // IV, RSC and reserved
+    key.extend_from_slice(&[mic; 16]);
+    key.extend_from_slice(&(key_data.len() as u16).to_be_bytes());
+    key.extend_from_slice(key_data);
+
+    let mut out = vec![0xaa, 0xaa, 0x03, 0x00, 0x00, 0x00, 0x88, 0x8e];
+    out.extend_from_slice(&[2, 3]); // 802.1X-2004, EAPOL-Key
+    out.extend_from_slice(&(key.len() as u16).to_be_bytes());
+    out.extend(key);
+    out
+}
+
+pub fn element(id: u8, data: &[u8]) -> Vec<u8> {
+    let mut out = vec![id, data.len() as u8];
+    out.extend_from_slice(data);
+    out
+}
+
+#[derive(Clone, Debug)]
+pub struct FrameBuilder {
+    pub frame_control: [u8; 2],
+    pub duration: [u8; 2],
+    pub addr1: [u8; 6],
+    pub addr2: [u8; 6],
+    pub addr3: [u8; 6],
+    pub seq_ctl: [u8; 2],
+    pub body: Vec<u8>,
+}
+
+impl FrameBuilder {
+    pub fn management(subtype: u8) -> FrameBuilder {
+        FrameBuilder {
+            frame_control: frame_control(0, subtype, 0),
+            duration: [0; 2],
+            addr1: BROADCAST,
+            addr2: [0; 6],
+            addr3: [0; 6],
+            seq_ctl: [0; 2],
+            body: Vec::new(),
+        }
+    }
+
+    pub fn data(flags: u8, addr1: [u8; 6], addr2: [u8; 6], addr3: [u8; 6]) -> FrameBuilder {
+        FrameBuilder {
+            frame_control: frame_control(2, 0, flags),
+            addr1,
+            addr2,
+            addr3,
+            ..FrameBuilder::management(0)
+        }
+    }
+
+    pub fn beacon(bssid: [u8; 6], interval: u16, cap_info: u16, elements: &[u8]) -> FrameBuilder {
+        let mut builder = FrameBuilder::management(8);
+        builder.addr2 = bssid;
+        builder.addr3 = bssid;
+        builder
+            .body
+            .extend_from_slice(&0x1122_3344_5566_7788u64.to_le_bytes());
+        builder.body.extend_from_slice(&interval.to_le_bytes());
+        builder.body.extend_from_slice(&cap_info.to_le_bytes());
+        builder.body.extend_from_slice(elements);
+        builder
+    }
+
+    pub fn probe_request(src: [u8; 6], elements: &[u8]) -> FrameBuilder {
+        let mut builder = FrameBuilder::management(4);
+        builder.addr2 = src;
+        builder.addr3 = BROADCAST;
+        builder.body.extend_from_slice(elements);
+        builder
+    }
+
+    pub fn dot11(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&self.frame_control);
+        out.extend_from_slice(&self.duration);
+        out.extend_from_slice(&self.addr1);
+        out.extend_from_slice(&self.addr2);
+        out.extend_from_slice(&self.addr3);
+        out.extend_from_slice(&self.seq_ctl);
+        out.extend_from_slice(&self.body);
+        out
+    }
+
+    pub fn with_radiotap(&self) -> Vec<u8> {
+        let mut out = radiotap();
+        out.extend(self.dot11());
+        out
+    }
+}
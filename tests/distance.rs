@@ -0,0 +1,66 @@
+use nearby::distance::*;
+use std::collections::HashMap;
+
+fn close(value: f32, expected: f32) -> bool {
+    (value - expected).abs() < 0.01
+}
+
+#[test]
+fn free_space_and_log_distance() {
+    // -40 dBm at 2412 MHz is about 1 meter away from a 0 dBm transmitter
+    let free_space = DistanceModel::default();
+    assert!(close(free_space.distance(-40, 2412.0, ""), 0.99));
+    assert!(free_space.distance(-128, 2412.0, "").is_finite());
+
+    let mut vendor_offsets = HashMap::new();
+    vendor_offsets.insert("Apple, Inc.".to_string(), 5.0);
+    let model = DistanceModel::LogDistance(LogDistance {
+        exponent: 2.0,
+        reference_rssi: -40.0,
+        vendor_offsets,
+    });
+    assert!(close(model.distance(-40, 2412.0, "Google, Inc."), 1.0));
+    assert!(close(model.distance(-60, 2412.0, "Google, Inc."), 10.0));
+    // A louder transmitter is farther than its signal suggests
+    assert!(close(model.distance(-55, 2412.0, "Apple, Inc."), 10.0));
+
+    let json: DistanceModel =
+        serde_json::from_str(r#"{"model": "LogDistance", "exponent": 3.5}"#).unwrap();
+    assert_eq!(
+        json,
+        DistanceModel::LogDistance(LogDistance {
+            exponent: 3.5,
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn calibration() {
+    // RSSI = -45 - 30 log10(d), and 4 dB louder for one vendor
+    let mut samples = Vec::new();
+    for distance in &[1.0, 2.0, 5.0, 10.0, 20.0] {
+        let signal = -45.0 - 30.0 * f32::log10(*distance);
+        samples.push(CalibrationSample {
+            signal: signal.round() as i8,
+            distance: *distance,
+            vendor: None,
+        });
+        samples.push(CalibrationSample {
+            signal: (signal + 4.0).round() as i8,
+            distance: *distance,
+            vendor: Some("Apple, Inc.".to_string()),
+        });
+    }
+
+    let model = LogDistance::fit(&samples).unwrap();
+    assert!((model.exponent - 3.0).abs() < 0.1);
+    assert!((model.reference_rssi + 45.0).abs() < 0.5);
+    assert!((model.vendor_offsets["Apple, Inc."] - 4.0).abs() < 0.5);
+    assert!((model.distance(-75, "Apple, Inc.") - 13.6).abs() < 1.0);
+
+    // One distance can't give the exponent
+    let same_distance = vec![samples[0].clone(), samples[1].clone()];
+    assert!(LogDistance::fit(&same_distance).is_err());
+    assert!(LogDistance::fit(&[]).is_err());
+}
@@ -0,0 +1,941 @@
+mod common;
+
+use common::*;
+use nearby::dot11::auth::*;
+use nearby::dot11::eapol::*;
+use nearby::dot11::elements::*;
+use nearby::dot11::header::*;
+use nearby::dot11::info::*;
+use nearby::dot11::mac::MacAddr;
+use nearby::dot11::phy::*;
+use nearby::dot11::qos::*;
+use nearby::dot11::security::*;
+use nearby::dot11::writer::Encode;
+use nearby::radiotap_writer::*;
+use nearby::scanner::Scanner;
+use proptest::prelude::*;
+use std::convert::TryFrom;
+
+fn mac() -> impl Strategy<Value = [u8; 6]> {
+    any::<[u8; 6]>()
+}
+
+fn ssid() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9 _-]{1,32}"
+}
+
+fn rates() -> impl Strategy<Value = Vec<u8>> {
+    // 1 to 54 Mbps in units of 500 kbps, optionally flagged as basic rates
+    prop::collection::vec((1u8..=108, any::<bool>()), 1..8).prop_map(|rates| {
+        rates
+            .into_iter()
+            .map(|(rate, basic)| if basic { rate | 0x80 } else { rate })
+            .collect()
+    })
+}
+
+proptest! {
+    #[test]
+    fn dot11_header_never_panics(input in prop::collection::vec(any::<u8>(), 0..512)) {
+        let _ = Dot11Header::from_bytes(&input);
+    }
+
+    #[test]
+    fn scanner_parse_never_panics(input in prop::collection::vec(any::<u8>(), 0..512)) {
+        let _ = Scanner::parse(&input);
+    }
+
+    #[test]
+    fn info_never_panics(input in prop::collection::vec(any::<u8>(), 0..256)) {
+        let _ = Beacon::from_bytes(&input);
+        let _ = ProbeRequest::from_bytes(&input);
+        let _ = ProbeResponse::from_bytes(&input);
+        let _ = AssociationRequest::from_bytes(&input);
+        let _ = AssociationResponse::from_bytes(&input);
+        let _ = ReassociationRequest::from_bytes(&input);
+        let _ = SSID::from_bytes(&input);
+        let _ = Country::from_bytes(&input);
+        let _ = RsnInfo::from_bytes(&input);
+        let _ = HtCapabilities::from_bytes(&input);
+        let _ = HtOperation::from_bytes(&input);
+        let _ = VhtCapabilities::from_bytes(&input);
+        let _ = VhtOperation::from_bytes(&input);
+        let _ = HeCapabilities::from_bytes(&input);
+        let _ = HeOperation::from_bytes(&input);
+        let _ = EhtCapabilities::from_bytes(&input);
+        let _ = EhtOperation::from_bytes(&input);
+    }
+
+    #[test]
+    fn frame_control_round_trip(frame_type in 0u8..3, subtype in 0u8..16, flags in any::<u8>()) {
+        let fc = FrameControl::from_bytes(&frame_control(frame_type, subtype, flags)).unwrap();
+
+        let expected_type = match frame_type {
+            0 => FrameType::Management,
+            1 => FrameType::Control,
+            _ => FrameType::Data,
+        };
+        prop_assert_eq!(fc.frame_type, expected_type);
+        prop_assert_eq!(fc.to_ds, flags & 0x01 > 0);
+        prop_assert_eq!(fc.from_ds, flags & 0x02 > 0);
+        prop_assert_eq!(fc.more_flag, flags & 0x04 > 0);
+        prop_assert_eq!(fc.retry, flags & 0x08 > 0);
+        prop_assert_eq!(fc.pwr_mgmt, flags & 0x10 > 0);
+        prop_assert_eq!(fc.more_data, flags & 0x20 > 0);
+        prop_assert_eq!(fc.wep, flags & 0x40 > 0);
+        prop_assert_eq!(fc.order, flags & 0x80 > 0);
+    }
+
+    #[test]
+    fn frame_control_rejects_unknown_version(version in 1u8..4, subtype in 0u8..16) {
+        let fc = frame_control(0, subtype, 0);
+        let result = FrameControl::from_bytes(&[fc[0] | version, fc[1]]);
+        prop_assert!(result.unwrap_err().is_malformed_frame());
+    }
+
+    #[test]
+    fn frame_addresses_round_trip(
+        addr1 in mac(), addr2 in mac(), addr3 in mac(), addr4 in mac(), flags in 0u8..4,
+    ) {
+        let mut input = Vec::new();
+        input.extend_from_slice(&addr1);
+        input.extend_from_slice(&addr2);
+        input.extend_from_slice(&addr3);
+        input.extend_from_slice(&[0; 2]); // sequence control
+        input.extend_from_slice(&addr4);
+
+        let fc = FrameControl::from_bytes(&frame_control(2, 0, flags)).unwrap();
+        let addresses = FrameAddresses::from_bytes(&input, fc).unwrap();
+        prop_assert_eq!(addresses.addr1, MacAddr(addr1));
+        prop_assert_eq!(addresses.addr2.unwrap(), MacAddr(addr2));
+        prop_assert_eq!(addresses.addr3.unwrap(), MacAddr(addr3));
+        // Only WDS and mesh frames carry Address 4
+        prop_assert_eq!(addresses.addr4, match flags {
+            3 => Some(MacAddr(addr4)),
+            _ => None,
+        });
+    }
+
+    #[test]
+    fn data_frame_addresses(
+        addr1 in mac(), addr2 in mac(), addr3 in mac(), addr4 in mac(), flags in 0u8..4,
+    ) {
+        let mut frame = frame_control(2, 0, flags).to_vec();
+        frame.extend_from_slice(&[0; 2]);
+        frame.extend_from_slice(&addr1);
+        frame.extend_from_slice(&addr2);
+        frame.extend_from_slice(&addr3);
+        frame.extend_from_slice(&[0; 2]);
+        if flags == 3 {
+            frame.extend_from_slice(&addr4);
+        }
+
+        let header = Dot11Header::from_bytes(&frame).unwrap();
+        let (a1, a2, a3, a4) =
+            (MacAddr(addr1), MacAddr(addr2), MacAddr(addr3), MacAddr(addr4));
+        // IEEE 802.11-2020 Table 9-30: (DA, SA, BSSID)
+        let (dst, src, bssid) = match flags {
+            0 => (&a1, &a2, &a3),
+            1 => (&a3, &a2, &a1), // To DS
+            2 => (&a1, &a3, &a2), // From DS
+            _ => (&a3, &a4, &a2), // WDS or mesh, the transmitter is used as BSSID
+        };
+        prop_assert_eq!(&header.receiver, &a1);
+        prop_assert_eq!(&header.transmitter, &a2);
+        prop_assert_eq!(&header.dst, dst);
+        prop_assert_eq!(&header.src, src);
+        prop_assert_eq!(&header.bssid, bssid);
+        prop_assert_eq!(header.to_bytes(), frame);
+    }
+
+    #[test]
+    fn mac_addr_round_trip(octets in mac()) {
+        let mac = MacAddr(octets);
+        prop_assert_eq!(mac.to_string().parse::<MacAddr>().unwrap(), mac);
+        prop_assert_eq!(mac.to_string().to_uppercase().replace(':', "-").parse::<MacAddr>().unwrap(), mac);
+
+        let json = serde_json::to_string(&mac).unwrap();
+        prop_assert_eq!(&json, &format!("\"{}\"", mac));
+        prop_assert_eq!(serde_json::from_str::<MacAddr>(&json).unwrap(), mac);
+    }
+
+    #[test]
+    fn elements_round_trip(
+        elements in prop::collection::vec((0u8..255, prop::collection::vec(any::<u8>(), 0..64)), 0..16)
+    ) {
+        let input: Vec<u8> = elements.iter().flat_map(|(id, data)| element(*id, data)).collect();
+        let parsed: Vec<(u8, Vec<u8>)> = Elements::new(&input)
+            .map(|element| (element.id, element.data.to_vec()))
+            .collect();
+
+        prop_assert_eq!(parsed, elements);
+    }
+
+    #[test]
+    fn beacon_round_trip(
+        bssid in mac(), ssid in ssid(), rates in rates(), channel in 1u8..165,
+        interval in any::<u16>(), cap_info in any::<u16>(),
+    ) {
+        // Elements in an unusual order, with an unknown one in between
+        let mut elements = element(3, &[channel]);
+        elements.extend(element(200, &[1, 2, 3]));
+        elements.extend(element(0, ssid.as_bytes()));
+        elements.extend(element(1, &rates));
+
+        let frame = FrameBuilder::beacon(bssid, interval, cap_info, &elements).dot11();
+        let header = Dot11Header::from_bytes(&frame).unwrap();
+
+        prop_assert_eq!(header.frame_control.frame_subtype, FrameSubType::Beacon);
+        prop_assert_eq!(&header.bssid, &MacAddr(bssid));
+        prop_assert_eq!(&header.src, &MacAddr(bssid));
+
+        let beacon = match header.info {
+            BodyInformation::Beacon(beacon) => beacon,
+            other => panic!("expected a beacon, got {:?}", other),
+        };
+        prop_assert_eq!(beacon.ssid.value, ssid);
+        prop_assert_eq!(beacon.interval, interval);
+        prop_assert_eq!(beacon.cap_info, cap_info);
+        prop_assert_eq!(beacon.current_channel, channel);
+        let expected: Vec<f32> = rates.iter().map(|rate| f32::from(rate & 0x7f) / 2.0).collect();
+        prop_assert_eq!(beacon.supported_rates, expected);
+        prop_assert_eq!(beacon.elements.len(), 4);
+    }
+
+    #[test]
+    fn probe_request_round_trip(src in mac(), ssid in ssid(), rates in rates()) {
+        let mut elements = element(0, ssid.as_bytes());
+        elements.extend(element(1, &rates));
+
+        let frame = FrameBuilder::probe_request(src, &elements).dot11();
+        let header = Dot11Header::from_bytes(&frame).unwrap();
+
+        prop_assert_eq!(&header.src, &MacAddr(src));
+        match header.info {
+            BodyInformation::ProbeRequest(probe) => prop_assert_eq!(probe.ssid.value, ssid),
+            other => panic!("expected a probe request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn encoded_beacon_matches_builder(
+        bssid in mac(), ssid in ssid(), channel in 1u8..165,
+        interval in any::<u16>(), cap_info in any::<u16>(),
+    ) {
+        let mut elements = element(0, ssid.as_bytes());
+        elements.extend(element(3, &[channel]));
+        let frame = FrameBuilder::beacon(bssid, interval, cap_info, &elements).dot11();
+
+        let header = Dot11Header::from_bytes(&frame).unwrap();
+        prop_assert_eq!(header.to_bytes(), frame);
+    }
+
+    #[test]
+    fn encoded_elements_round_trip(
+        ssid in ssid(), rates in rates(), channel in 1u8..165, eht_width in 0u16..5,
+    ) {
+        let elements = vec![
+            InformationElement::Ssid(SSID::new(&ssid)),
+            InformationElement::SupportedRates(decode_rates(&rates)),
+            InformationElement::DsParameterSet(channel),
+            InformationElement::Country(Country { country_code: "PT ".into() }),
+            InformationElement::HtOperation(HtOperation {
+                primary_channel: channel,
+                secondary_channel_offset: SecondaryChannelOffset::Below,
+                any_channel_width: true,
+            }),
+            InformationElement::EhtOperation(EhtOperation {
+                params: 0x01, // EHT Operation Information present
+                channel_width: Some(20 << eht_width),
+            }),
+            InformationElement::Unknown { id: 200, ext_id: None, data: vec![1, 2, 3] },
+        ];
+        let beacon = Beacon::new(0, 100, 0x0011, elements.clone());
+        let decoded = Beacon::from_bytes(&beacon.to_bytes()).unwrap();
+
+        prop_assert_eq!(&decoded.elements, &elements);
+        prop_assert_eq!(decoded.ssid.value, ssid);
+        prop_assert_eq!(decoded.current_channel, channel);
+        prop_assert_eq!(decoded.phy.channel_width, 20 << eht_width);
+    }
+
+    #[test]
+    fn radiotap_round_trip(
+        tsft in any::<u64>(), signal in any::<i8>(), noise in any::<i8>(), channel in 1u8..15,
+        antenna in any::<u8>(),
+    ) {
+        let radiotap = RadiotapHeader {
+            tsft: Some(tsft),
+            flags: Some(0),
+            rate: Some(6.0),
+            channel: Some(Channel::from_number(channel)),
+            antenna_signal: Some(signal),
+            antenna_noise: Some(noise),
+            antenna: Some(antenna),
+        };
+        let frame = FrameBuilder::probe_request([2; 6], &element(0, b"nearby")).dot11();
+        let data = radiotap.encapsulate(&frame);
+
+        let (tap_data, header) = Scanner::parse(&data).unwrap();
+        prop_assert_eq!(tap_data.tsft.unwrap().value, tsft);
+        prop_assert_eq!(tap_data.antenna_signal.unwrap().value, signal);
+        prop_assert_eq!(tap_data.antenna_noise.unwrap().value, noise);
+        prop_assert_eq!(tap_data.antenna.unwrap().value, antenna);
+        prop_assert_eq!(
+            tap_data.channel.unwrap().freq,
+            Channel::from_number(channel).frequency
+        );
+        prop_assert_eq!(header.to_bytes(), frame);
+    }
+
+    #[test]
+    fn truncated_beacon_is_malformed(bssid in mac(), ssid in ssid(), cut in 0usize..36) {
+        let elements = element(0, ssid.as_bytes());
+        let frame = FrameBuilder::beacon(bssid, 100, 0, &elements).with_radiotap();
+
+        // Cutting inside the radiotap, MAC header or fixed beacon fields must fail cleanly
+        let result = Scanner::parse(&frame[..frame.len().min(cut + 8)]);
+        prop_assert!(result.unwrap_err().is_malformed_frame());
+    }
+}
+
+#[test]
+fn mesh_proxied_frame() {
+    let (ra, ta, mesh_da, mesh_sa, end_da, end_sa) =
+        ([1; 6], [2; 6], [3; 6], [4; 6], [5; 6], [6; 6]);
+
+    let mut frame = frame_control(2, 8, 0x03).to_vec(); // QoS Data, To DS and From DS
+    frame.extend_from_slice(&[0; 2]);
+    for addr in &[ra, ta, mesh_da] {
+        frame.extend_from_slice(addr);
+    }
+    frame.extend_from_slice(&[0; 2]);
+    frame.extend_from_slice(&mesh_sa);
+    frame.extend_from_slice(&0x0100u16.to_le_bytes()); // Mesh Control Present
+    frame.extend_from_slice(&[0x02, 31, 1, 0, 0, 0]); // Address Extension Mode 2, TTL, sequence
+    frame.extend_from_slice(&end_da);
+    frame.extend_from_slice(&end_sa);
+
+    let header = Dot11Header::from_bytes(&frame).unwrap();
+    assert_eq!(header.src, MacAddr(mesh_sa));
+    assert_eq!(header.dst, MacAddr(mesh_da));
+    let mesh = header.mesh_control.as_ref().unwrap();
+    assert_eq!(mesh.ttl, 31);
+    assert_eq!(mesh.addr5, Some(MacAddr(end_da)));
+    assert_eq!(mesh.addr6, Some(MacAddr(end_sa)));
+    assert_eq!(header.to_bytes(), frame);
+}
+
+#[test]
+fn control_frames() {
+    let (ra, ta) = ([0x02, 1, 1, 1, 1, 1], [0x00, 2, 2, 2, 2, 2]);
+    let control = |subtype: u8, duration: [u8; 2], addresses: &[[u8; 6]], body: &[u8]| {
+        let mut frame = frame_control(1, subtype, 0).to_vec();
+        frame.extend_from_slice(&duration);
+        for addr in addresses {
+            frame.extend_from_slice(addr);
+        }
+        frame.extend_from_slice(body);
+        frame
+    };
+
+    // RTS with bandwidth signalling sets the I/G bit of the TA
+    let rts = control(11, [0; 2], &[ra, [0x01, 2, 2, 2, 2, 2]], &[]);
+    let header = Dot11Header::from_bytes(&rts).unwrap();
+    assert_eq!(header.frame_control.frame_subtype, FrameSubType::Rts);
+    assert_eq!(header.receiver, MacAddr(ra));
+    assert_eq!(header.transmitter, MacAddr(ta));
+
+    for (subtype, expected) in &[(12, FrameSubType::Cts), (13, FrameSubType::Ack)] {
+        let frame = control(*subtype, [0; 2], &[ra], &[]);
+        let header = Dot11Header::from_bytes(&frame).unwrap();
+        assert_eq!(header.frame_control.frame_subtype, *expected);
+        assert_eq!(header.receiver, MacAddr(ra));
+        assert!(header.transmitter.is_unspecified());
+        assert_eq!(header.to_bytes(), frame);
+    }
+
+    // PS-Poll: Association ID in the duration field and the BSSID as receiver
+    let ps_poll = control(10, [0x05, 0xc0], &[ra, ta], &[]);
+    let header = Dot11Header::from_bytes(&ps_poll).unwrap();
+    assert_eq!(header.association_id(), Some(5));
+    assert_eq!(header.bssid, MacAddr(ra));
+
+    // Compressed Block Ack for TID 6 starting at sequence number 100
+    let bitmap = [0xff, 0x0f, 0, 0, 0, 0, 0, 0];
+    let mut body = vec![0x05, 0x60];
+    body.extend_from_slice(&(100u16 << 4).to_le_bytes());
+    body.extend_from_slice(&bitmap);
+    let block_ack = control(9, [0; 2], &[ra, ta], &body);
+    let header = Dot11Header::from_bytes(&block_ack).unwrap();
+    match header.info {
+        BodyInformation::BlockAck(ref ba) => {
+            assert_eq!(ba.tid, 6);
+            assert_eq!(ba.starting_sequence, 100);
+            assert!(ba.is_compressed());
+            assert_eq!(ba.bitmap, bitmap);
+        }
+        ref other => panic!("expected a block ack, got {:?}", other),
+    }
+    assert_eq!(header.to_bytes(), block_ack);
+
+    // A CTS is 10 bytes long, an RTS without its TA is truncated
+    assert!(Dot11Header::from_bytes(&control(11, [0; 2], &[ra], &[]))
+        .unwrap_err()
+        .is_malformed_frame());
+}
+
+#[test]
+fn qos_data_with_he_control() {
+    let mut frame = frame_control(2, 8, 0x81).to_vec(); // QoS Data, To DS, +HTC
+    frame.extend_from_slice(&[0; 2]);
+    for addr in &[[1; 6], [2; 6], [3; 6]] {
+        frame.extend_from_slice(addr);
+    }
+    frame.extend_from_slice(&[0; 2]);
+    frame.extend_from_slice(&0x0096u16.to_le_bytes()); // TID 6, EOSP, A-MSDU present
+
+    // HE variant: OM subfield (ID 1, 12 bits) then ONES padding
+    let he_control: u32 = 0b11 | (1 << 2) | (0x0abc << 6) | (u32::MAX << 18);
+    frame.extend_from_slice(&he_control.to_le_bytes());
+    frame.extend_from_slice(&[0xaa, 0xaa, 0x03]); // body
+
+    let header = Dot11Header::from_bytes(&frame).unwrap();
+    let qos = header.qos.unwrap();
+    assert_eq!(qos.tid, 6);
+    assert_eq!(qos.access_category(), AccessCategory::Voice);
+    assert!(qos.eosp && qos.amsdu_present);
+    assert_eq!(
+        header.ht_control,
+        Some(HtControl::He(vec![AControl {
+            id: 1,
+            info: 0x0abc
+        }]))
+    );
+    assert_eq!(header.to_bytes(), &frame[..frame.len() - 3]);
+}
+
+#[test]
+fn eapol_key_messages() {
+    let ap = [0x02; 6];
+    let client = [0x04; 6];
+    let pmkid = [
+        0xdd, 0x14, 0x00, 0x0f, 0xac, 0x04, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99,
+        0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff, 0x01,
+    ];
+    let rsn = element(48, &[0x01, 0x00, 0x00, 0x0f, 0xac, 0x04]);
+    let frames = [
+        (
+            0x02,
+            ap,
+            client,
+            eapol_key(0x008a, 1, 0xa1, 0, &pmkid),
+            HandshakeMessage::M1,
+        ),
+        (
+            0x01,
+            client,
+            ap,
+            eapol_key(0x010a, 1, 0x51, 0x33, &rsn),
+            HandshakeMessage::M2,
+        ),
+        (
+            0x02,
+            ap,
+            client,
+            eapol_key(0x13ca, 2, 0xa1, 0x44, &[0x5a; 56]),
+            HandshakeMessage::M3,
+        ),
+        (
+            0x01,
+            client,
+            ap,
+            eapol_key(0x030a, 2, 0, 0x55, &[]),
+            HandshakeMessage::M4,
+        ),
+    ];
+
+    for (flags, transmitter, receiver, body, message) in frames.iter() {
+        let mut builder = FrameBuilder::data(*flags, *receiver, *transmitter, [0x06; 6]);
+        builder.body = body.clone();
+        let header = Dot11Header::from_bytes(&builder.dot11()).unwrap();
+
+        let key = match header.info {
+            BodyInformation::EapolKey(ref key) => key.clone(),
+            ref info => panic!("{:?} parsed as {:?}", message, info),
+        };
+        assert_eq!(key.message(), Some(*message));
+        assert_eq!(key.key_version(), 2);
+        assert_eq!(key.mic.len(), 16);
+        assert_eq!(key.to_bytes(), *body);
+        assert_eq!(key.eapol_with_zero_mic().len(), body.len() - 8);
+        match message {
+            HandshakeMessage::M1 => assert_eq!(key.pmkid().unwrap()[..2], [0x11, 0x22]),
+            _ => assert_eq!(key.pmkid(), None),
+        }
+    }
+
+    // Protected frames are never decoded
+    let mut builder = FrameBuilder::data(0x41, ap, client, ap);
+    builder.body = eapol_key(0x008a, 1, 0xa1, 0, &[]);
+    let header = Dot11Header::from_bytes(&builder.dot11()).unwrap();
+    assert!(matches!(header.info, BodyInformation::UnHandled(_)));
+
+    // Truncated key data
+    let mut builder = FrameBuilder::data(0x02, client, ap, ap);
+    builder.body = eapol_key(0x008a, 1, 0xa1, 0, &pmkid);
+    builder.body.truncate(builder.body.len() - 4);
+    assert!(Dot11Header::from_bytes(&builder.dot11()).is_err());
+}
+
+#[test]
+fn authentication_and_deauthentication() {
+    let mut builder = FrameBuilder::management(11);
+    let challenge = element(16, &[0x42; 128]);
+    builder.body = [&[1, 0, 2, 0, 0, 0][..], &challenge].concat(); // Shared Key, seq 2
+    let header = Dot11Header::from_bytes(&builder.dot11()).unwrap();
+    match header.info {
+        BodyInformation::Authentication(ref auth) => {
+            assert_eq!(auth.algorithm, AuthAlgorithm::SharedKey);
+            assert_eq!(auth.sequence, 2);
+            assert_eq!(auth.status, StatusCode::Success);
+            assert!(!auth.completes());
+            assert_eq!(auth.elements.len(), 1);
+            assert_eq!(auth.to_bytes(), builder.body);
+        }
+        ref info => panic!("unexpected body {:?}", info),
+    }
+
+    // SAE Commit rejected with an anti-clogging token request, the group and token
+    // aren't elements
+    builder.body = vec![3, 0, 1, 0, 76, 0, 19, 0, 0xaa, 0xbb];
+    let header = Dot11Header::from_bytes(&builder.dot11()).unwrap();
+    match header.info {
+        BodyInformation::Authentication(ref auth) => {
+            assert_eq!(auth.algorithm, AuthAlgorithm::Sae);
+            assert_eq!(auth.status, StatusCode::AntiCloggingTokenRequired);
+            assert_eq!(auth.sae_fields, [19, 0, 0xaa, 0xbb]);
+            assert_eq!(auth.to_bytes(), builder.body);
+        }
+        ref info => panic!("unexpected body {:?}", info),
+    }
+
+    for (subtype, reason) in [(12, 7), (10, 8), (12, 0x1234)].iter() {
+        let mut builder = FrameBuilder::management(*subtype);
+        builder.body = (*reason as u16).to_le_bytes().to_vec();
+        let header = Dot11Header::from_bytes(&builder.dot11()).unwrap();
+        let deauth = match header.info {
+            BodyInformation::Deauthentication(ref deauth) if *subtype == 12 => deauth,
+            BodyInformation::Disassociation(ref disassoc) if *subtype == 10 => disassoc,
+            ref info => panic!("unexpected body {:?}", info),
+        };
+        assert_eq!(deauth.reason.code(), *reason as u16);
+        assert_eq!(deauth.to_bytes(), builder.body);
+    }
+    assert_eq!(
+        ReasonCode::from_code(7),
+        ReasonCode::Class3FrameFromNonAssocStation
+    );
+
+    // Robust management frames are encrypted
+    let mut builder = FrameBuilder::management(12);
+    builder.frame_control[1] |= 0x40;
+    builder.body = vec![0x5a; 24];
+    let header = Dot11Header::from_bytes(&builder.dot11()).unwrap();
+    assert!(matches!(header.info, BodyInformation::UnHandled(_)));
+
+    builder.frame_control[1] = 0;
+    builder.body = vec![7];
+    assert!(Dot11Header::from_bytes(&builder.dot11()).is_err());
+}
+
+#[test]
+fn mac_addr_flags() {
+    let broadcast: MacAddr = "ff:ff:ff:ff:ff:ff".parse().unwrap();
+    assert!(broadcast.is_broadcast() && broadcast.is_group() && !broadcast.is_multicast());
+
+    let ipv6_multicast: MacAddr = "33:33:00:00:00:01".parse().unwrap();
+    assert!(ipv6_multicast.is_multicast() && !ipv6_multicast.is_unicast());
+
+    let random: MacAddr = "da:a1:19:00:11:22".parse().unwrap();
+    assert!(random.is_unicast() && random.is_locally_administered());
+    assert_eq!(random.oui(), [0xda, 0xa1, 0x19]);
+
+    assert!(MacAddr::default().is_unspecified());
+    assert!("00:11:22:33:44".parse::<MacAddr>().is_err());
+    assert!("00:11:22:33:44:gg".parse::<MacAddr>().is_err());
+
+    let bytes = [0xda, 0xa1, 0x19, 0x00, 0x11, 0x22, 0x33];
+    assert_eq!(MacAddr::try_from(&bytes[..]).unwrap(), random);
+    assert!(MacAddr::try_from(&bytes[..5])
+        .unwrap_err()
+        .is_malformed_frame());
+}
+
+#[test]
+fn rsn_wpa2_psk() {
+    let rsn = [
+        0x01, 0x00, // version
+        0x00, 0x0f, 0xac, 0x04, // group cipher CCMP
+        0x01, 0x00, 0x00, 0x0f, 0xac, 0x04, // pairwise CCMP
+        0x01, 0x00, 0x00, 0x0f, 0xac, 0x02, // AKM PSK
+        0x00, 0x00, // capabilities
+    ];
+    let rsn = RsnInfo::from_bytes(&rsn).unwrap();
+    let security = Security::new(0x0011, Some(rsn), None);
+
+    assert_eq!(security.label, "WPA2-PSK");
+    assert!(!security.weak);
+}
+
+// Cipher and AKM suites are given by their suite type
+struct Suites {
+    group: u8,
+    pairwise: &'static [u8],
+    akms: &'static [u8],
+    capabilities: u16,
+}
+
+impl Suites {
+    fn body(&self, oui: [u8; 3]) -> Vec<u8> {
+        let mut body = vec![0x01, 0x00];
+        body.extend_from_slice(&oui);
+        body.push(self.group);
+        for list in &[self.pairwise, self.akms] {
+            body.extend_from_slice(&(list.len() as u16).to_le_bytes());
+            for suite in list.iter() {
+                body.extend_from_slice(&oui);
+                body.push(*suite);
+            }
+        }
+        body.extend_from_slice(&self.capabilities.to_le_bytes());
+        body
+    }
+}
+
+const fn suites(
+    group: u8, pairwise: &'static [u8], akms: &'static [u8], capabilities: u16,
+) -> Suites {
+    Suites {
+        group,
+        pairwise,
+        akms,
+        capabilities,
+    }
+}
+
+#[test]
+fn security_labels() {
+    // Ciphers: 1 WEP-40, 2 TKIP, 4 CCMP, 9 GCMP-256. AKMs: 1 802.1X, 2 PSK,
+    // 5 802.1X-SHA256, 8 SAE, 12 Suite B 192, 18 OWE. 0x00c0: MFP required.
+    let cases = [
+        (0x0001, None, None, "Open", true),
+        (0x0011, None, None, "WEP", true),
+        (
+            0x0011,
+            None,
+            Some(suites(2, &[2], &[2], 0)),
+            "WPA-PSK",
+            true,
+        ),
+        (
+            0x0011,
+            None,
+            Some(suites(2, &[2], &[1], 0)),
+            "WPA-Enterprise",
+            true,
+        ),
+        (
+            0x0011,
+            Some(suites(2, &[4, 2], &[2], 0)),
+            Some(suites(2, &[2], &[2], 0)),
+            "WPA/WPA2-PSK",
+            true,
+        ),
+        (
+            0x0011,
+            Some(suites(4, &[4], &[2], 0)),
+            None,
+            "WPA2-PSK",
+            false,
+        ),
+        // TKIP as pairwise or group cipher
+        (
+            0x0011,
+            Some(suites(2, &[2], &[2], 0)),
+            None,
+            "WPA2-PSK",
+            true,
+        ),
+        (
+            0x0011,
+            Some(suites(2, &[4], &[2], 0)),
+            None,
+            "WPA2-PSK",
+            true,
+        ),
+        (
+            0x0011,
+            Some(suites(1, &[4], &[2], 0)),
+            None,
+            "WPA2-PSK",
+            true,
+        ),
+        (
+            0x0011,
+            Some(suites(4, &[4], &[1], 0)),
+            None,
+            "WPA2-Enterprise",
+            false,
+        ),
+        (
+            0x0011,
+            Some(suites(4, &[4], &[5], 0x00c0)),
+            None,
+            "WPA3-Enterprise",
+            false,
+        ),
+        (
+            0x0011,
+            Some(suites(9, &[9], &[12], 0x00c0)),
+            None,
+            "WPA3-Enterprise-192",
+            false,
+        ),
+        (
+            0x0011,
+            Some(suites(4, &[4], &[8], 0x00c0)),
+            None,
+            "WPA3-SAE",
+            false,
+        ),
+        (
+            0x0011,
+            Some(suites(4, &[4], &[2, 8], 0x0080)),
+            None,
+            "WPA2/WPA3-Transition",
+            false,
+        ),
+        (
+            0x0011,
+            Some(suites(4, &[4], &[18], 0x00c0)),
+            None,
+            "OWE",
+            false,
+        ),
+    ];
+
+    for (cap_info, rsn, wpa, label, weak) in &cases {
+        let rsn = rsn
+            .as_ref()
+            .map(|rsn| RsnInfo::from_bytes(&rsn.body([0x00, 0x0f, 0xac])).unwrap());
+        let wpa = wpa.as_ref().map(|wpa| {
+            let vendor = [&[0x00, 0x50, 0xf2, 0x01][..], &wpa.body([0x00, 0x50, 0xf2])].concat();
+            RsnInfo::from_wpa_vendor_bytes(&vendor).unwrap()
+        });
+        let pmf_required = rsn
+            .as_ref()
+            .is_some_and(|rsn| rsn.capabilities & 0x0040 > 0);
+        let security = Security::new(*cap_info, rsn, wpa);
+
+        assert_eq!(security.label, *label);
+        assert_eq!(security.weak, *weak, "{}", label);
+        assert_eq!(security.privacy, *cap_info & 0x0010 > 0);
+        assert_eq!(security.pmf_required, pmf_required);
+    }
+}
+
+#[test]
+fn rsn_encode_round_trip() {
+    let rsn = RsnInfo {
+        version: 1,
+        group_cipher: CipherSuite::Tkip,
+        pairwise_ciphers: vec![CipherSuite::Ccmp128, CipherSuite::Tkip],
+        akm_suites: vec![AkmSuite::Psk, AkmSuite::Sae],
+        capabilities: 0x0080,
+        pmkids: vec![[7; 16]],
+        group_mgmt_cipher: Some(CipherSuite::BipCmac128),
+    };
+    assert_eq!(RsnInfo::from_bytes(&rsn.to_bytes()).unwrap(), rsn);
+
+    let wpa = RsnInfo::from_wpa_vendor_bytes(&rsn.to_wpa_vendor_bytes()).unwrap();
+    assert_eq!(wpa.pairwise_ciphers, rsn.pairwise_ciphers);
+    assert_eq!(wpa.akm_suites[0], AkmSuite::Psk);
+}
+
+#[test]
+fn rsn_truncated_list_is_an_error() {
+    // Two pairwise ciphers announced, only one present
+    let rsn = [
+        0x01, 0x00, 0x00, 0x0f, 0xac, 0x04, 0x02, 0x00, 0x00, 0x0f, 0xac, 0x04,
+    ];
+    assert!(RsnInfo::from_bytes(&rsn).unwrap_err().is_malformed_frame());
+}
+
+#[test]
+fn oversized_ssid_stops_element_iteration() {
+    // SSID claims 32 bytes but only 3 follow
+    let input = [0x00, 32, b'a', b'b', b'c'];
+    assert_eq!(Elements::new(&input).count(), 0);
+    assert!(SSID::from_bytes(&input).unwrap_err().is_malformed_frame());
+}
+
+fn ht_capabilities(cap_info: u16, streams: usize) -> Vec<u8> {
+    let mut data = cap_info.to_le_bytes().to_vec();
+    data.push(0); // A-MPDU parameters
+    let mut rx_mcs = [0; 10];
+    rx_mcs[..streams].copy_from_slice(&[0xff; 4][..streams]);
+    data.extend_from_slice(&rx_mcs);
+    data.extend_from_slice(&[0; 13]);
+    element(45, &data)
+}
+
+fn ht_operation(primary_channel: u8, info: u8) -> Vec<u8> {
+    element(61, &[&[primary_channel, info][..], &[0; 20]].concat())
+}
+
+fn extension(id: u8, data: &[u8]) -> Vec<u8> {
+    element(255, &[&[id][..], data].concat())
+}
+
+// MCS 0-9 for each spatial stream, the others not supported
+fn mcs_map(streams: u32) -> [u8; 2] {
+    let map = (0..8).fold(0u16, |map, ss| {
+        map | if ss < streams { 0b10 } else { 0b11 } << (ss * 2)
+    });
+    map.to_le_bytes()
+}
+
+fn he_capabilities(streams: u32) -> Vec<u8> {
+    let map = mcs_map(streams);
+    extension(35, &[&[0; 17][..], &map, &map].concat())
+}
+
+fn phy(elements: &[Vec<u8>]) -> PhyInfo {
+    PhyInfo::from_parameters(&TaggedParameters::from_bytes(&elements.concat()).unwrap())
+}
+
+fn close(rate: f32, expected: f32) -> bool {
+    (rate - expected).abs() < 0.1
+}
+
+#[test]
+fn phy_legacy_and_ht() {
+    // 1, 2, 5.5, 11 (basic), 6, 9, 12 and 18 Mbps
+    let rates = element(1, &[0x82, 0x84, 0x8b, 0x96, 0x0c, 0x12, 0x18, 0x24]);
+    let legacy = phy(&[rates.clone(), element(3, &[6])]);
+    assert_eq!(legacy.standard, "802.11g");
+    assert_eq!(legacy.wifi_generation, 3);
+    assert_eq!(legacy.channel_width, 20);
+    assert_eq!(legacy.spatial_streams, 1);
+    assert!(close(legacy.max_rate, 18.0));
+
+    // 40 MHz and short GI, secondary channel above, 2 spatial streams
+    let ht = phy(&[rates, ht_capabilities(0x0062, 2), ht_operation(6, 0x05)]);
+    assert_eq!(ht.standard, "802.11n");
+    assert_eq!(ht.wifi_generation, 4);
+    assert_eq!(ht.channel_width, 40);
+    assert_eq!(ht.secondary_channel_offset, SecondaryChannelOffset::Above);
+    assert_eq!(ht.spatial_streams, 2);
+    assert!(close(ht.max_rate, 300.0));
+
+    // A secondary channel without the any channel width flag stays at 20 MHz
+    let ht = phy(&[ht_capabilities(0x0000, 1), ht_operation(6, 0x01)]);
+    assert_eq!(ht.channel_width, 20);
+    assert_eq!(ht.secondary_channel_offset, SecondaryChannelOffset::Above);
+    assert!(close(ht.max_rate, 65.0));
+}
+
+#[test]
+fn phy_vht_80mhz_two_streams() {
+    let vht_capabilities = [
+        &0x0000_0020u32.to_le_bytes()[..], // short GI at 80 MHz
+        &mcs_map(2),
+        &[0; 2],
+        &mcs_map(2),
+        &[0; 2],
+    ]
+    .concat();
+    let vht = phy(&[
+        ht_capabilities(0x0062, 2),
+        ht_operation(36, 0x07),
+        element(191, &vht_capabilities),
+        element(192, &[1, 42, 0, 0xff, 0xff]),
+    ]);
+    assert_eq!(vht.standard, "802.11ac");
+    assert_eq!(vht.wifi_generation, 5);
+    assert_eq!(vht.channel_width, 80);
+    assert_eq!(vht.secondary_channel_offset, SecondaryChannelOffset::Below);
+    assert_eq!(vht.spatial_streams, 2);
+    assert!(close(vht.max_rate, 866.6));
+}
+
+#[test]
+fn phy_he_and_eht() {
+    // 160 MHz from the VHT Operation information carried by the HE Operation
+    let he_operation = extension(36, &[0x00, 0x40, 0x00, 1, 0xfc, 0xff, 1, 42, 50]);
+    let he = phy(&[ht_capabilities(0x0062, 2), he_capabilities(4), he_operation]);
+    assert_eq!(he.standard, "802.11ax");
+    assert_eq!(he.wifi_generation, 6);
+    assert_eq!(he.channel_width, 160);
+    assert_eq!(he.spatial_streams, 4);
+    assert!(close(he.max_rate, 4804.0));
+
+    // 6 GHz: 80 MHz from the HE Operation, 320 MHz from the EHT Operation
+    let he_6ghz = extension(36, &[0x00, 0x00, 0x02, 1, 0xfc, 0xff, 37, 0x02, 39, 0, 0]);
+    let eht_capabilities = extension(108, &[0; 11]);
+    let eht = phy(&[
+        he_capabilities(1),
+        he_6ghz.clone(),
+        eht_capabilities.clone(),
+    ]);
+    assert_eq!(eht.standard, "802.11be");
+    assert_eq!(eht.wifi_generation, 7);
+    assert_eq!(eht.channel_width, 80);
+    assert!(close(eht.max_rate, 720.6));
+
+    let eht_operation = extension(106, &[0x01, 0xfc, 0xff, 0xff, 0xff, 0x04, 31, 63]);
+    let eht = phy(&[he_capabilities(1), he_6ghz, eht_capabilities, eht_operation]);
+    assert_eq!(eht.channel_width, 320);
+    assert_eq!(eht.secondary_channel_offset, SecondaryChannelOffset::None);
+    assert_eq!(eht.spatial_streams, 1);
+    assert!(close(eht.max_rate, 2882.4));
+}
+
+#[test]
+fn long_elements_are_fragmented() {
+    let vendor: Vec<u8> = (0..600).map(|byte| byte as u8).collect();
+    let extension: Vec<u8> = (0..300).map(|byte| byte as u8).collect();
+    let elements = vec![
+        InformationElement::Unknown {
+            id: 221,
+            ext_id: None,
+            data: vendor,
+        },
+        InformationElement::Unknown {
+            id: 255,
+            ext_id: Some(200),
+            data: extension,
+        },
+        InformationElement::DsParameterSet(6),
+    ];
+    let beacon = Beacon::new(0, 100, 0x0011, elements.clone());
+    let bytes = beacon.to_bytes();
+
+    // 600 bytes: 255 in the element, then Fragment elements of 255 and 90 bytes
+    let raw: Vec<(u8, usize)> = Elements::new(&bytes[12..])
+        .map(|element| (element.id, element.data.len()))
+        .collect();
+    assert_eq!(
+        raw,
+        [
+            (221, 255),
+            (242, 255),
+            (242, 90),
+            (255, 254),
+            (242, 46),
+            (3, 1)
+        ]
+    );
+    assert_eq!(Beacon::from_bytes(&bytes).unwrap().elements, elements);
+}
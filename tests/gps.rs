@@ -0,0 +1,179 @@
+mod common;
+
+use common::*;
+use nearby::dot11::mac::MacAddr;
+use nearby::gps::*;
+use nearby::mapper::*;
+use nearby::radiotap_writer::RadiotapHeader;
+use nearby::scanner::Scanner;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+const BSSID: [u8; 6] = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+// 2020-09-13T12:26:40Z
+const START: u64 = 1_600_000_000;
+
+// Appends the checksum to the sentence
+fn nmea(body: &str) -> String {
+    let checksum = body.bytes().fold(0, |sum, byte| sum ^ byte);
+    format!("${}*{:02X}\n", body, checksum)
+}
+
+fn beacon(signal: i8) -> Vec<u8> {
+    let beacon = FrameBuilder::beacon(BSSID, 100, 1, &element(0, b"wardriving"));
+    let radiotap = RadiotapHeader {
+        antenna_signal: Some(signal),
+        ..Default::default()
+    };
+    radiotap.encapsulate(&beacon.dot11())
+}
+
+#[test]
+fn nmea_replay_tags_access_points() {
+    let log = [
+        nmea("GPRMC,122640,A,4807.038,N,01131.000,E,022.4,084.4,130920,003.1,W"),
+        nmea("GPGGA,122640,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,"),
+        nmea("GPRMC,122650,A,4807.138,N,01131.000,E,022.4,084.4,130920,003.1,W"),
+        // No fix, then a corrupted sentence
+        nmea("GPRMC,122655,V,,,,,,,130920,,"),
+        "$GPRMC,122657,A,4807.238,N,01131.000,E,022.4,084.4,130920,003.1,W*00\n".to_string(),
+        nmea("GPRMC,122700,A,4807.238,S,01131.000,W,022.4,084.4,130920,003.1,W"),
+    ]
+    .concat();
+    let track = NmeaTrack::from_reader(log.as_bytes()).unwrap();
+    assert_eq!(track.fixes.len(), 4);
+    assert_eq!(track.fixes[0].0, Duration::from_secs(START));
+    let first = track.fixes[0].1.as_ref().unwrap();
+    assert!((first.latitude - 48.1173).abs() < 1e-6);
+    assert!((first.longitude - 11.516_667).abs() < 1e-6);
+    assert_eq!(first.altitude, Some(545.4));
+    assert_eq!(first.time.as_deref(), Some("2020-09-13T12:26:40Z"));
+    let last = track.fixes[3].1.as_ref().unwrap();
+    assert!(last.latitude < 0.0 && last.longitude < 0.0);
+    assert_eq!(last.altitude, None);
+    assert_eq!(track.fix_at(Duration::from_secs(START - 1)), None);
+    assert_eq!(
+        track.fix_at(Duration::from_secs(START + 12)),
+        track.fixes[1].1
+    );
+    // The receiver lost the fix, and the log ended a while ago
+    assert_eq!(track.fixes[2], (Duration::from_secs(START + 15), None));
+    assert_eq!(track.fix_at(Duration::from_secs(START + 17)), None);
+    assert_eq!(
+        track.fix_at(Duration::from_secs(START + 25)),
+        track.fixes[3].1
+    );
+    assert_eq!(track.fix_at(Duration::from_secs(START + 31)), None);
+
+    let mut mapper = Mapper::new().unwrap();
+    mapper.gps = Some(Gps::Nmea(track.clone()));
+    let mut scanner = Scanner::new(mapper, false, None);
+    for (secs, signal) in &[(0, -70), (10, -50), (20, -60)] {
+        scanner
+            .process_at(&beacon(*signal), Duration::from_secs(START + secs))
+            .unwrap();
+    }
+
+    let access_point = &scanner.mapper.net_map[&MacAddr(BSSID)];
+    let sightings = access_point.gps.as_ref().unwrap();
+    assert_eq!(sightings.count, 3);
+    // The access point is estimated where its signal was the strongest
+    assert_eq!(sightings.strongest.signal, -50);
+    assert_eq!(Some(sightings.strongest.fix.clone()), track.fixes[1].1);
+    assert_eq!(sightings.last.signal, -60);
+    assert_eq!(Some(sightings.last.fix.clone()), track.fixes[3].1);
+
+    // Frames captured without a fix aren't tagged
+    scanner
+        .process_at(&beacon(-40), Duration::from_secs(START + 17))
+        .unwrap();
+    let sightings = scanner.mapper.net_map[&MacAddr(BSSID)].gps.clone().unwrap();
+    assert_eq!(sightings.count, 3);
+
+    // Without a position the access points aren't tagged
+    let mut scanner = Scanner::new(Mapper::new().unwrap(), false, None);
+    scanner.process(&beacon(-50)).unwrap();
+    assert_eq!(scanner.mapper.net_map[&MacAddr(BSSID)].gps, None);
+}
+
+// Waits for the background thread to read the reports
+fn wait_for_fix(gpsd: &Gpsd, present: bool) -> Option<Fix> {
+    for _ in 0..100 {
+        let fix = gpsd.fix();
+        if fix.is_some() == present {
+            return fix;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    gpsd.fix()
+}
+
+#[test]
+fn gpsd_stand_in() {
+    const FIX: &str = r#"{"class":"TPV","device":"/dev/ttyUSB0","mode":3,"time":"2020-09-13T12:26:40.000Z","lat":48.1173,"lon":11.516667,"altHAE":592.3,"altMSL":545.4}"#;
+    const NO_FIX: &str = r#"{"class":"TPV","device":"/dev/ttyUSB0","mode":1}"#;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    // Each message is a batch of reports, the connection closes with the channel
+    let (reports, next) = mpsc::channel::<Vec<&str>>();
+    let gpsd = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut watch = String::new();
+        BufReader::new(stream.try_clone().unwrap())
+            .read_line(&mut watch)
+            .unwrap();
+        for batch in next {
+            for report in batch {
+                writeln!(stream, "{}", report).unwrap();
+            }
+        }
+        watch
+    });
+
+    let client = Gpsd::connect(&addr).unwrap();
+    reports
+        .send(vec![
+            r#"{"class":"VERSION","release":"3.22","proto_major":3,"proto_minor":14}"#,
+            NO_FIX,
+            FIX,
+            r#"{"class":"SKY","device":"/dev/ttyUSB0","satellites":[]}"#,
+        ])
+        .unwrap();
+    let fix = wait_for_fix(&client, true);
+    assert_eq!(
+        fix,
+        Some(Fix {
+            latitude: 48.1173,
+            longitude: 11.516667,
+            altitude: Some(545.4),
+            time: Some("2020-09-13T12:26:40.000Z".into()),
+        })
+    );
+
+    let mut mapper = Mapper::new().unwrap();
+    mapper.gps = Some(Gps::Gpsd(client.clone()));
+    let mut scanner = Scanner::new(mapper, false, None);
+    scanner.process(&beacon(-42)).unwrap();
+    let sightings = scanner.mapper.net_map[&MacAddr(BSSID)].gps.clone().unwrap();
+    assert_eq!(sightings.strongest.fix, fix.unwrap());
+    assert_eq!(sightings.strongest.signal, -42);
+
+    // The receiver loses the fix: the last position isn't kept
+    reports.send(vec![NO_FIX]).unwrap();
+    assert_eq!(wait_for_fix(&client, false), None);
+    scanner.process(&beacon(-30)).unwrap();
+    let sightings = scanner.mapper.net_map[&MacAddr(BSSID)].gps.clone().unwrap();
+    assert_eq!(sightings.count, 1);
+
+    // Neither once gpsd is gone
+    reports.send(vec![FIX]).unwrap();
+    assert!(wait_for_fix(&client, true).is_some());
+    drop(reports);
+    let watch = gpsd.join().unwrap();
+    assert!(watch.starts_with("?WATCH="));
+    assert_eq!(wait_for_fix(&client, false), None);
+}
@@ -0,0 +1,343 @@
+mod common;
+
+use common::*;
+use nearby::dot11::auth::*;
+use nearby::dot11::mac::MacAddr;
+use nearby::mapper::*;
+use nearby::scanner::Scanner;
+use nearby::signal::*;
+use std::time::Duration;
+
+const BSSID: [u8; 6] = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+const CLIENT: [u8; 6] = [0x00, 0x66, 0x77, 0x88, 0x99, 0xaa];
+const START: u64 = 1_600_000_000;
+
+fn qos_data(flags: u8, addresses: [[u8; 6]; 3], tid: u8) -> Vec<u8> {
+    let mut frame = radiotap();
+    frame.extend_from_slice(&frame_control(2, 8, flags));
+    frame.extend_from_slice(&[0; 2]);
+    for addr in &addresses {
+        frame.extend_from_slice(addr);
+    }
+    frame.extend_from_slice(&[0; 2]);
+    frame.extend_from_slice(&[tid, 0]);
+    frame
+}
+
+#[test]
+fn client_traffic_per_access_category() {
+    let mut scanner = Scanner::new(Mapper::new().unwrap(), false, None);
+    let beacon = FrameBuilder::beacon(BSSID, 100, 0x0011, &element(0, b"nearby"));
+    assert!(scanner.process(&beacon.with_radiotap()).unwrap().is_some());
+
+    // Upstream voice (To DS) and downstream video and best effort (From DS)
+    let server = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05];
+    scanner
+        .process(&qos_data(0x01, [BSSID, CLIENT, server], 6))
+        .unwrap();
+    scanner
+        .process(&qos_data(0x02, [CLIENT, BSSID, server], 5))
+        .unwrap();
+    scanner
+        .process(&qos_data(0x02, [CLIENT, BSSID, server], 0))
+        .unwrap();
+
+    let access_point = &scanner.mapper.net_map[&MacAddr(BSSID)];
+    let client = access_point
+        .nodes
+        .iter()
+        .find(|node| node.mac == MacAddr(CLIENT))
+        .unwrap();
+    assert_eq!(client.properties.traffic.voice, 1);
+    assert_eq!(client.properties.traffic.video, 1);
+    assert_eq!(client.properties.traffic.best_effort, 1);
+    assert_eq!(client.properties.traffic.background, 0);
+
+    // The wired server behind the access point isn't a wireless client
+    assert!(access_point
+        .nodes
+        .iter()
+        .all(|node| node.mac != MacAddr(server)));
+}
+
+#[test]
+fn wpa_handshake_capture() {
+    let mut scanner = Scanner::new(Mapper::new().unwrap(), false, None);
+    let beacon = FrameBuilder::beacon(BSSID, 100, 0x0011, &element(0, b"nearby"));
+    scanner.process(&beacon.with_radiotap()).unwrap();
+
+    let mut pmkid = vec![0xdd, 0x14, 0x00, 0x0f, 0xac, 0x04];
+    pmkid.extend_from_slice(&[0x77; 16]);
+    let rsn = element(48, &[0x01, 0x00, 0x00, 0x0f, 0xac, 0x04]);
+    let messages = [
+        (0x02, eapol_key(0x008a, 1, 0xa1, 0, &pmkid)),
+        (0x01, eapol_key(0x010a, 1, 0x51, 0x33, &rsn)),
+        (0x02, eapol_key(0x13ca, 2, 0xa1, 0x44, &[0x5a; 56])),
+        (0x01, eapol_key(0x030a, 2, 0, 0x55, &[])),
+    ];
+    for (i, (flags, body)) in messages.iter().enumerate() {
+        let mut builder = match flags {
+            0x01 => FrameBuilder::data(*flags, BSSID, CLIENT, BSSID),
+            _ => FrameBuilder::data(*flags, CLIENT, BSSID, BSSID),
+        };
+        builder.body = body.clone();
+        let timestamp = Duration::from_millis(1_000 + i as u64);
+        scanner
+            .process_at(&builder.with_radiotap(), timestamp)
+            .unwrap();
+    }
+
+    let client = scanner.mapper.net_map[&MacAddr(BSSID)]
+        .nodes
+        .iter()
+        .find(|node| node.mac == MacAddr(CLIENT))
+        .unwrap();
+    let state = client.properties.handshake.clone().unwrap();
+    assert!(state.is_complete() && state.pmkid);
+
+    let crackable = scanner.handshakes.crackable();
+    assert_eq!(crackable.len(), 1);
+    let (handshake, ssid) = crackable[0];
+    assert_eq!(ssid, "nearby");
+    assert_eq!(handshake.frames.iter().flatten().count(), 4);
+
+    let lines = handshake.hashcat_lines(ssid);
+    assert_eq!(
+        lines[0],
+        format!(
+            "WPA*01*{}*001122334455*0066778899aa*6e6561726279***",
+            "77".repeat(16)
+        )
+    );
+    let fields: Vec<&str> = lines[1].split('*').collect();
+    assert_eq!(fields[..2], ["WPA", "02"]);
+    assert_eq!(fields[2], "33".repeat(16)); // MIC of M2
+    assert_eq!(
+        fields[3..6],
+        ["001122334455", "0066778899aa", "6e6561726279"]
+    );
+    assert_eq!(fields[6], "a1".repeat(32)); // ANonce from M1
+    assert_eq!(fields[8], "00"); // M1 + M2
+
+    // The EAPOL packet of M2 with a zeroed MIC
+    let mut eapol = messages[1].1[8..].to_vec();
+    eapol[81..97].copy_from_slice(&[0; 16]);
+    assert_eq!(
+        fields[7],
+        eapol
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    );
+}
+
+#[test]
+fn client_connection_lifecycle() {
+    let mut scanner = Scanner::new(Mapper::new().unwrap(), false, None);
+    let beacon = FrameBuilder::beacon(BSSID, 100, 0x0011, &element(0, b"nearby"));
+    scanner.process(&beacon.with_radiotap()).unwrap();
+
+    let other = [0x00, 0x66, 0x77, 0x88, 0x99, 0xbb];
+    let frames = [
+        (11, CLIENT, BSSID, vec![0, 0, 1, 0, 0, 0]), // Open System request
+        (11, BSSID, CLIENT, vec![0, 0, 2, 0, 0, 0]), // and response
+        (0, CLIENT, BSSID, vec![0x11, 0, 10, 0]),    // Association request
+        (1, BSSID, CLIENT, vec![0x11, 0, 0, 0, 1, 0xc0]),
+        (11, other, BSSID, vec![0, 0, 1, 0, 0, 0]),
+        (11, BSSID, other, vec![0, 0, 2, 0, 17, 0]), // AP full
+        (12, BSSID, BROADCAST, vec![7, 0]),
+    ];
+    for (i, (subtype, transmitter, receiver, body)) in frames.iter().enumerate() {
+        let mut builder = FrameBuilder::management(*subtype);
+        builder.addr1 = *receiver;
+        builder.addr2 = *transmitter;
+        builder.addr3 = BSSID;
+        builder.body = body.clone();
+        let timestamp = Duration::from_secs(1_600_000_000 + i as u64);
+        scanner
+            .process_at(&builder.with_radiotap(), timestamp)
+            .unwrap();
+    }
+
+    let access_point = &scanner.mapper.net_map[&MacAddr(BSSID)];
+    let node = |mac| {
+        access_point
+            .nodes
+            .iter()
+            .find(|node| node.mac == MacAddr(mac))
+            .unwrap()
+    };
+
+    let client = &node(CLIENT).properties;
+    let kinds: Vec<ConnectionEventKind> = client.events.iter().map(|event| event.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            ConnectionEventKind::Authentication {
+                algorithm: AuthAlgorithm::OpenSystem,
+                sequence: 1,
+                status: StatusCode::Success
+            },
+            ConnectionEventKind::Authentication {
+                algorithm: AuthAlgorithm::OpenSystem,
+                sequence: 2,
+                status: StatusCode::Success
+            },
+            ConnectionEventKind::AssociationRequest,
+            ConnectionEventKind::AssociationResponse(StatusCode::Success),
+            ConnectionEventKind::Deauthentication {
+                reason: ReasonCode::Class3FrameFromNonAssocStation,
+                from_client: false
+            },
+        ]
+    );
+    assert_eq!(client.events[0].timestamp, 1_600_000_000);
+    assert_eq!(client.connection, Some(ConnectionState::Deauthenticated));
+
+    // The rejected client never got authenticated, but was disconnected too
+    let other = &node(other).properties;
+    assert_eq!(other.events.len(), 3);
+    assert_eq!(other.connection, Some(ConnectionState::Deauthenticated));
+    assert!(node(BSSID).properties.events.is_empty());
+}
+
+fn process_at(scanner: &mut Scanner, frame: &[u8], secs: u64) {
+    scanner
+        .process_at(frame, Duration::from_secs(START + secs))
+        .unwrap();
+}
+
+#[test]
+fn device_presence_and_departures() {
+    let mut mapper = Mapper::new().unwrap();
+    mapper.expiry = Some(Duration::from_secs(60));
+    let mut scanner = Scanner::new(mapper, false, None);
+    let beacon = FrameBuilder::beacon(BSSID, 100, 0x0011, &element(0, b"nearby"));
+    let upstream = qos_data(0x01, [BSSID, CLIENT, BSSID], 0);
+    process_at(&mut scanner, &beacon.with_radiotap(), 0);
+    process_at(&mut scanner, &upstream, 10);
+    process_at(&mut scanner, &upstream, 20);
+    // Frames to the client show it is still there, but aren't its own
+    process_at(&mut scanner, &qos_data(0x02, [CLIENT, BSSID, BSSID], 0), 30);
+    process_at(&mut scanner, &beacon.with_radiotap(), 50);
+
+    let access_point = &scanner.mapper.net_map[&MacAddr(BSSID)];
+    assert_eq!(
+        access_point.presence,
+        Presence {
+            first_seen: START,
+            last_seen: START + 50,
+            frames: 3,
+        }
+    );
+    let client = access_point
+        .nodes
+        .iter()
+        .find(|node| node.mac == MacAddr(CLIENT))
+        .unwrap();
+    let client_presence = Presence {
+        first_seen: START + 10,
+        last_seen: START + 30,
+        frames: 2,
+    };
+    assert_eq!(client.properties.presence, client_presence);
+    assert!(scanner.take_departures().is_empty());
+
+    // The client left, the access point keeps beaconing
+    process_at(&mut scanner, &beacon.with_radiotap(), 95);
+    assert_eq!(
+        scanner.take_departures(),
+        [Departure {
+            mac: MacAddr(CLIENT),
+            kind: DeviceKind::Client,
+            bssid: Some(MacAddr(BSSID)),
+            presence: client_presence,
+        }]
+    );
+    let access_point = &scanner.mapper.net_map[&MacAddr(BSSID)];
+    assert_eq!(access_point.nodes.len(), 1);
+    assert!(access_point.links.is_empty());
+
+    // Then the access point is gone too
+    process_at(&mut scanner, &upstream, 200);
+    let departures = scanner.take_departures();
+    assert_eq!(departures.len(), 1);
+    assert_eq!(departures[0].kind, DeviceKind::AccessPoint);
+    assert_eq!(departures[0].presence.last_seen, START + 95);
+    assert!(scanner.mapper.net_map.is_empty());
+}
+
+// Combined signal, then one radiotap namespace per antenna
+fn per_antenna_radiotap(combined: i8, antennas: [i8; 2]) -> Vec<u8> {
+    let mut header = vec![0, 0, 21, 0];
+    header.extend_from_slice(&0xa000_0020u32.to_le_bytes());
+    header.extend_from_slice(&0xa000_0820u32.to_le_bytes());
+    header.extend_from_slice(&0x0000_0820u32.to_le_bytes());
+    header.extend_from_slice(&[combined as u8, antennas[0] as u8, 0, antennas[1] as u8, 1]);
+    header
+}
+
+#[test]
+fn signal_statistics_per_antenna() {
+    let radiotap = per_antenna_radiotap(-60, [-62, -65]);
+    assert_eq!(
+        RadiotapSignals::from_bytes(&radiotap),
+        RadiotapSignals {
+            combined: Some(-60),
+            antennas: vec![
+                AntennaSignal {
+                    antenna: 0,
+                    signal: -62
+                },
+                AntennaSignal {
+                    antenna: 1,
+                    signal: -65
+                },
+            ],
+        }
+    );
+
+    let mut scanner = Scanner::new(Mapper::new().unwrap(), false, None);
+    let beacon = FrameBuilder::beacon(BSSID, 100, 0x0011, &element(0, b"nearby"));
+    process_at(&mut scanner, &beacon.with_radiotap(), 0);
+
+    let upstream = FrameBuilder::data(0x01, BSSID, CLIENT, BSSID).dot11();
+    for (combined, antennas) in &[(-60, [-62, -65]), (-50, [-52, -55]), (-70, [-72, -75])] {
+        let frame = [per_antenna_radiotap(*combined, *antennas), upstream.clone()].concat();
+        process_at(&mut scanner, &frame, 1);
+    }
+
+    let access_point = &scanner.mapper.net_map[&MacAddr(BSSID)];
+    let client = access_point
+        .nodes
+        .iter()
+        .find(|node| node.mac == MacAddr(CLIENT))
+        .unwrap();
+    let rssi = &client.properties.rssi;
+    assert_eq!(
+        (rssi.rssi.min, rssi.rssi.max, rssi.rssi.samples),
+        (-70, -50, 3)
+    );
+    assert!((rssi.rssi.mean + 60.0).abs() < 0.01);
+    assert!((rssi.rssi.ewma + 60.4).abs() < 0.01);
+    assert_eq!(client.properties.quality, SignalQuality::Fair);
+    assert_eq!(client.properties.signal, -60);
+
+    let antennas: Vec<(u8, i8, i8, u64)> = rssi
+        .antennas
+        .iter()
+        .map(|antenna| {
+            (
+                antenna.antenna,
+                antenna.rssi.min,
+                antenna.rssi.max,
+                antenna.rssi.samples,
+            )
+        })
+        .collect();
+    assert_eq!(antennas, [(0, -72, -52, 3), (1, -75, -55, 3)]);
+
+    // The access point only received those frames, its signal comes from the beacon
+    assert_eq!(access_point.rssi.rssi.samples, 0);
+    assert_eq!(access_point.quality, SignalQuality::Unknown);
+}
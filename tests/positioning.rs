@@ -0,0 +1,147 @@
+mod common;
+
+use common::*;
+use nearby::distance::*;
+use nearby::dot11::mac::MacAddr;
+use nearby::mapper::*;
+use nearby::positioning::*;
+use nearby::radiotap_writer::RadiotapHeader;
+use nearby::scanner::Scanner;
+use nearby::util::create_netjson;
+
+const DEVICE: [u8; 6] = [0x00, 0x66, 0x77, 0x88, 0x99, 0xaa];
+
+fn anchor(x: f64, y: f64, target: (f64, f64)) -> Anchor {
+    Anchor {
+        x,
+        y,
+        distance: (x - target.0).hypot(y - target.1),
+    }
+}
+
+#[test]
+fn least_squares_trilateration() {
+    let target = (3.0, 4.0);
+    let anchors = [
+        anchor(0.0, 0.0, target),
+        anchor(10.0, 0.0, target),
+        anchor(0.0, 10.0, target),
+        anchor(10.0, 10.0, target),
+    ];
+    let (x, y, uncertainty) = trilaterate(&anchors).unwrap();
+    assert!((x - 3.0).abs() < 1e-6 && (y - 4.0).abs() < 1e-6);
+    assert!(uncertainty < 1e-6);
+
+    // Noisy ranges give a larger uncertainty
+    let mut noisy = anchors;
+    noisy[0].distance += 1.0;
+    noisy[3].distance -= 1.0;
+    let (x, y, uncertainty) = trilaterate(&noisy).unwrap();
+    assert!((x - 3.0).abs() < 1.0 && (y - 4.0).abs() < 1.0);
+    assert!(uncertainty > 0.1 && uncertainty < 1.0);
+
+    // Two sensors, or sensors on a line, can't tell the position
+    assert_eq!(trilaterate(&anchors[..2]), None);
+    let aligned = [
+        anchor(0.0, 0.0, target),
+        anchor(5.0, 0.0, target),
+        anchor(10.0, 0.0, target),
+    ];
+    assert_eq!(trilaterate(&aligned), None);
+}
+
+#[test]
+fn geojson_from_sensor_observations() {
+    let deployment: Deployment = serde_json::from_str(
+        r#"{
+            "sensors": [
+                {"name": "hall", "x": 0, "y": 0},
+                {"name": "kitchen", "x": 10, "y": 0},
+                {"name": "office", "x": 0, "y": 10}
+            ],
+            "origin": [2.0, 48.0],
+            "distance": {"model": "LogDistance", "exponent": 2.0, "reference_rssi": -40.0}
+        }"#,
+    )
+    .unwrap();
+    let mut trilateration = Trilateration::new(deployment);
+
+    // RSSI = -40 - 20 log10(d), for a device at (3, 4)
+    let stream = r#"
+        {"sensor": "hall", "mac": "00:66:77:88:99:aa", "signal": -54, "vendor": "Apple, Inc."}
+        {"sensor": "kitchen", "mac": "00:66:77:88:99:aa", "signal": -58}
+        {"sensor": "office", "mac": "00:66:77:88:99:aa", "signal": -58}
+        {"sensor": "office", "mac": "00:66:77:88:99:aa", "signal": -57}
+        {"sensor": "hall", "mac": "00:11:22:33:44:55", "signal": -50}
+    "#;
+    for observation in Observation::from_reader(stream.as_bytes()).unwrap() {
+        trilateration.observe(&observation).unwrap();
+    }
+
+    let positions = trilateration.positions();
+    // The second device was heard by one sensor only
+    assert_eq!(positions.len(), 1);
+    let position = &positions[0];
+    assert_eq!(position.mac, MacAddr(DEVICE));
+    assert_eq!(position.vendor, "Apple, Inc.");
+    assert_eq!(position.sensors, 3);
+    assert!((position.x - 3.0).abs() < 1.0 && (position.y - 4.0).abs() < 1.0);
+
+    let geojson = serde_json::to_value(trilateration.geojson()).unwrap();
+    assert_eq!(geojson["type"], "FeatureCollection");
+    let feature = &geojson["features"][0];
+    assert_eq!(feature["geometry"]["type"], "Point");
+    assert_eq!(feature["properties"]["mac"], "00:66:77:88:99:aa");
+    // A few meters north east of the origin
+    let longitude = feature["geometry"]["coordinates"][0].as_f64().unwrap();
+    let latitude = feature["geometry"]["coordinates"][1].as_f64().unwrap();
+    assert!(longitude > 2.0 && longitude < 2.0001);
+    assert!(latitude > 48.0 && latitude < 48.0001);
+
+    let unknown = Observation {
+        sensor: "garage".into(),
+        mac: MacAddr(DEVICE),
+        signal: -60,
+        vendor: String::new(),
+        freq: None,
+    };
+    assert!(trilateration.observe(&unknown).is_err());
+    assert_eq!(
+        trilateration.deployment.distance,
+        DistanceModel::LogDistance(LogDistance {
+            exponent: 2.0,
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn mapper_outputs() {
+    let mut scanner = Scanner::new(Mapper::new().unwrap(), false, None);
+    let elements = [&element(0, b"home")[..], &element(3, &[6])].concat();
+    let beacon = FrameBuilder::beacon([0x00, 0x11, 0x22, 0x33, 0x44, 0x55], 100, 1, &elements);
+    let radiotap = RadiotapHeader {
+        antenna_signal: Some(-50),
+        ..Default::default()
+    };
+    scanner
+        .process(&radiotap.encapsulate(&beacon.dot11()))
+        .unwrap();
+
+    let netjson: NetworkCollection =
+        serde_json::from_str(&create_netjson(scanner.mapper).unwrap()).unwrap();
+    let deployment = Deployment {
+        sensors: vec![Sensor {
+            name: "hall".into(),
+            x: 0.0,
+            y: 0.0,
+            output: None,
+        }],
+        ..Default::default()
+    };
+    let mut trilateration = Trilateration::new(deployment);
+    trilateration.add_netjson("hall", &netjson).unwrap();
+    // Heard, but one sensor can't locate it
+    assert!(trilateration.positions().is_empty());
+    assert!(trilateration.add_netjson("garage", &netjson).is_err());
+}
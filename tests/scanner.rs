@@ -0,0 +1,92 @@
+mod common;
+
+use common::*;
+use nearby::dot11::mac::MacAddr;
+use nearby::errors::*;
+use nearby::mapper::{DeviceKind, Mapper};
+use nearby::scanner::*;
+use nearby::source::{MemorySource, Next, PacketSource};
+use std::time::Duration;
+
+const BSSID: [u8; 6] = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+const CLIENT: [u8; 6] = [0x00, 0x66, 0x77, 0x88, 0x99, 0xaa];
+const START: u64 = 1_600_000_000;
+
+fn at(secs: u64) -> Duration {
+    Duration::from_secs(START + secs)
+}
+
+#[test]
+fn scan_synthetic_frames() {
+    let beacon = FrameBuilder::beacon(BSSID, 100, 0x0011, &element(0, b"nearby"));
+    let probe = FrameBuilder::probe_request(CLIENT, &element(0, b""));
+    let mut source = MemorySource::new(vec![(beacon.with_radiotap(), at(0))]);
+    source.push(beacon.with_radiotap(), at(30));
+    source.push(probe.with_radiotap(), at(40));
+
+    let mut scanner = Scanner::new(Mapper::new().unwrap(), false, None);
+    match scanner.step(&mut source).unwrap() {
+        ScanEvent::AccessPoint(ap) => {
+            assert_eq!(ap.ssid, "nearby");
+            assert_eq!(ap.router_id, MacAddr(BSSID));
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+    assert!(matches!(
+        scanner.step(&mut source).unwrap(),
+        ScanEvent::Frame
+    ));
+    assert!(matches!(
+        scanner.step(&mut source).unwrap(),
+        ScanEvent::Frame
+    ));
+    assert!(matches!(
+        scanner.step(&mut source).unwrap(),
+        ScanEvent::Finished
+    ));
+
+    // The frames are mapped at their capture time
+    let access_point = &scanner.mapper.net_map[&MacAddr(BSSID)];
+    assert_eq!(access_point.presence.first_seen, START);
+    assert_eq!(access_point.presence.last_seen, START + 30);
+
+    // run stops once the source is exhausted
+    let mut source = MemorySource::new(vec![(beacon.with_radiotap(), at(0)); 3]);
+    let mut scanner = Scanner::new(Mapper::new().unwrap(), false, None);
+    scanner.run(&mut source).unwrap();
+    assert_eq!(scanner.mapper.net_map.len(), 1);
+    assert!(matches!(
+        scanner.step(&mut source).unwrap(),
+        ScanEvent::Finished
+    ));
+}
+
+// A channel where nothing is heard
+struct Quiet;
+
+impl PacketSource for Quiet {
+    fn next_frame(&mut self) -> Result<Next<'_>> {
+        Ok(Next::Timeout)
+    }
+}
+
+#[test]
+fn timeouts_expire_devices() {
+    let beacon = FrameBuilder::beacon(BSSID, 100, 0x0011, &element(0, b"nearby"));
+    let mut mapper = Mapper::new().unwrap();
+    mapper.expiry = Some(Duration::from_secs(60));
+    let mut scanner = Scanner::new(mapper, false, None);
+    scanner.process_at(&beacon.with_radiotap(), at(0)).unwrap();
+    assert!(scanner.take_departures().is_empty());
+
+    // The access point was last seen years ago
+    assert!(matches!(
+        scanner.step(&mut Quiet).unwrap(),
+        ScanEvent::Timeout
+    ));
+    let departures = scanner.take_departures();
+    assert_eq!(departures.len(), 1);
+    assert_eq!(departures[0].mac, MacAddr(BSSID));
+    assert_eq!(departures[0].kind, DeviceKind::AccessPoint);
+    assert!(scanner.mapper.net_map.is_empty());
+}
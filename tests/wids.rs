@@ -0,0 +1,361 @@
+mod common;
+
+use common::*;
+use nearby::dot11::header::Dot11Header;
+use nearby::dot11::mac::{MacAddr, Oui};
+use nearby::mapper::Mapper;
+use nearby::radiotap_writer::RadiotapHeader;
+use nearby::scanner::Scanner;
+use nearby::wids::evil_twin::*;
+use nearby::wids::flood::*;
+use nearby::wids::karma::*;
+use nearby::wids::*;
+use radiotap::Radiotap;
+use std::time::Duration;
+
+const BSSID: [u8; 6] = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+const CLIENT: [u8; 6] = [0x00, 0x66, 0x77, 0x88, 0x99, 0xaa];
+
+fn disconnection(subtype: u8, transmitter: [u8; 6], receiver: [u8; 6]) -> Dot11Header {
+    let mut builder = FrameBuilder::management(subtype);
+    builder.addr1 = receiver;
+    builder.addr2 = transmitter;
+    builder.addr3 = BSSID;
+    builder.body = vec![7, 0];
+    Dot11Header::from_bytes(&builder.dot11()).unwrap()
+}
+
+fn no_radiotap() -> Radiotap {
+    Radiotap::from_bytes(&radiotap()).unwrap()
+}
+
+fn floods(wids: &mut Wids, header: &Dot11Header, frames: u64, interval: Duration) -> Vec<Alert> {
+    let start = Duration::from_secs(1_600_000_000);
+    for i in 0..frames {
+        wids.inspect(&no_radiotap(), header, start + interval * i as u32);
+    }
+    wids.take_alerts()
+}
+
+#[test]
+fn deauthentication_flood_per_client() {
+    let mut wids = Wids::new();
+    let deauth = disconnection(12, BSSID, CLIENT);
+
+    let alerts = floods(&mut wids, &deauth, 25, Duration::from_millis(100));
+    // One alert per window, not one per frame
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(
+        alerts[0].kind,
+        AlertKind::DeauthenticationFlood(Flood {
+            bssid: MacAddr(BSSID),
+            client: Some(MacAddr(CLIENT)),
+            frames: 10,
+            window: 10,
+        })
+    );
+    assert_eq!(alerts[0].timestamp, 1_600_000_000);
+    assert!(alerts[0].to_string().contains("client 00:66:77:88:99:aa"));
+
+    // Spoofed in the other direction, by the client
+    let disassoc = disconnection(10, CLIENT, BSSID);
+    let alerts = floods(&mut wids, &disassoc, 10, Duration::from_millis(100));
+    assert!(matches!(
+        alerts[..],
+        [Alert {
+            kind: AlertKind::DisassociationFlood(_),
+            ..
+        }]
+    ));
+}
+
+#[test]
+fn occasional_deauthentication_is_not_a_flood() {
+    let mut wids = Wids::new();
+    let deauth = disconnection(12, BSSID, CLIENT);
+
+    // 30 frames, but never more than 5 inside the 10 seconds window
+    assert!(floods(&mut wids, &deauth, 30, Duration::from_secs(2)).is_empty());
+}
+
+#[test]
+fn broadcast_and_bss_wide_floods() {
+    let mut wids = Wids::new();
+    let broadcast = disconnection(12, BSSID, BROADCAST);
+
+    let alerts = floods(&mut wids, &broadcast, 5, Duration::from_millis(100));
+    assert_eq!(alerts.len(), 1);
+    assert!(alerts[0].to_string().contains("broadcast"));
+
+    // Many clients, below the threshold of each one but not of the BSS
+    let mut wids = Wids::new();
+    let start = Duration::from_secs(1_600_000_000);
+    for i in 0..30u8 {
+        let deauth = disconnection(12, BSSID, [0x00, 0x66, 0x77, 0x88, 0x99, i]);
+        let timestamp = start + Duration::from_millis(u64::from(i) * 100);
+        wids.inspect(&no_radiotap(), &deauth, timestamp);
+    }
+    let alerts = wids.take_alerts();
+    assert_eq!(alerts.len(), 1);
+    match alerts[0].kind {
+        AlertKind::DeauthenticationFlood(ref flood) => assert_eq!(flood.client, None),
+        ref kind => panic!("unexpected alert {:?}", kind),
+    }
+}
+
+#[test]
+fn protected_deauthentication_is_ignored() {
+    let mut builder = FrameBuilder::management(12);
+    builder.frame_control[1] |= 0x40;
+    builder.addr1 = CLIENT;
+    builder.addr2 = BSSID;
+    builder.addr3 = BSSID;
+    builder.body = vec![0x5a; 24];
+    let header = Dot11Header::from_bytes(&builder.dot11()).unwrap();
+
+    let mut wids = Wids::new();
+    wids.flood = FloodDetector::new(FloodConfig {
+        threshold: 1,
+        ..Default::default()
+    });
+    assert!(floods(&mut wids, &header, 10, Duration::from_millis(10)).is_empty());
+}
+
+#[test]
+fn alert_sinks() {
+    let alert = Alert {
+        timestamp: 1_600_000_000,
+        kind: AlertKind::DisassociationFlood(Flood {
+            bssid: MacAddr(BSSID),
+            client: None,
+            frames: 30,
+            window: 10,
+        }),
+    };
+
+    let path = std::env::temp_dir().join(format!("nearby-alerts-{}.jsonl", std::process::id()));
+    let mut sink = JsonLinesSink::new(&path).unwrap();
+    sink.emit(&alert).unwrap();
+    sink.emit(&alert).unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let lines: Vec<Alert> = content
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines, [alert.clone(), alert.clone()]);
+
+    let shared = SharedAlerts::new(1);
+    let mut sink = shared.clone();
+    sink.emit(&alert).unwrap();
+    sink.emit(&alert).unwrap();
+    assert_eq!(shared.snapshot(), [alert]);
+}
+
+fn rogue_alerts(scanner: &mut Scanner, beacon: &FrameBuilder, signal: i8) -> Vec<Deviation> {
+    let radiotap = RadiotapHeader {
+        antenna_signal: Some(signal),
+        ..Default::default()
+    };
+    scanner
+        .process_at(
+            &radiotap.encapsulate(&beacon.dot11()),
+            Duration::from_secs(1_600_000_000),
+        )
+        .unwrap();
+    scanner
+        .take_alerts()
+        .into_iter()
+        .map(|alert| match alert.kind {
+            AlertKind::RogueAccessPoint(rogue) => rogue.deviation,
+            kind => panic!("unexpected alert {:?}", kind),
+        })
+        .collect()
+}
+
+#[test]
+fn rogue_access_points() {
+    let baseline: Vec<AuthorizedNetwork> = serde_json::from_str(
+        r#"[{"ssid": "corp", "bssids": ["00:11:22:33:44:55"], "ouis": ["00-0B-86"],
+             "channels": [6], "security": ["WPA2-PSK"]}]"#,
+    )
+    .unwrap();
+    assert_eq!(baseline[0].ouis, [Oui([0x00, 0x0b, 0x86])]);
+    // Malformed OUIs are rejected when the baseline loads
+    for oui in &["000B86", "00:0B:86:", "00:0B:8G"] {
+        let json = format!(r#"[{{"ssid": "corp", "ouis": ["{}"]}}]"#, oui);
+        assert!(serde_json::from_str::<Vec<AuthorizedNetwork>>(&json).is_err());
+    }
+    let mut scanner = Scanner::new(Mapper::new().unwrap(), false, None);
+    scanner.wids.evil_twin = EvilTwinDetector::new(Baseline::new(baseline));
+
+    let rsn_psk = element(
+        48,
+        &[
+            1, 0, 0, 0x0f, 0xac, 4, 1, 0, 0, 0x0f, 0xac, 4, 1, 0, 0, 0x0f, 0xac, 2, 0, 0,
+        ],
+    );
+    let elements = |channel: u8, security: &[u8]| {
+        [&element(0, b"corp"), &element(3, &[channel]), security].concat()
+    };
+
+    // The authorized access point, and another one from an allowed vendor
+    let corp = FrameBuilder::beacon(BSSID, 100, 0x0011, &elements(6, &rsn_psk));
+    assert!(rogue_alerts(&mut scanner, &corp, -50).is_empty());
+    let vendor = [0x00, 0x0b, 0x86, 0x01, 0x02, 0x03];
+    let corp_vendor = FrameBuilder::beacon(vendor, 100, 0x0011, &elements(6, &rsn_psk));
+    assert!(rogue_alerts(&mut scanner, &corp_vendor, -60).is_empty());
+
+    // An open copy on another channel, reported once
+    let rogue = [0x02, 0xaa, 0xbb, 0xcc, 0xdd, 0xee];
+    let open_copy = FrameBuilder::beacon(rogue, 100, 0x0001, &elements(11, &[]));
+    assert_eq!(
+        rogue_alerts(&mut scanner, &open_copy, -40),
+        [
+            Deviation::OpenCopy,
+            Deviation::UnknownBssid,
+            Deviation::UnexpectedChannel(11)
+        ]
+    );
+    assert!(rogue_alerts(&mut scanner, &open_copy, -40).is_empty());
+
+    // The authorized BSSID spoofed by a much closer transmitter
+    for _ in 0..5 {
+        assert!(rogue_alerts(&mut scanner, &corp, -80).is_empty());
+    }
+    assert_eq!(
+        rogue_alerts(&mut scanner, &corp, -30),
+        [Deviation::SignalJump {
+            average: -70,
+            signal: -30
+        }]
+    );
+}
+
+#[test]
+fn open_copy_without_baseline() {
+    let mut scanner = Scanner::new(Mapper::new().unwrap(), false, None);
+    let rsn = element(48, &[1, 0, 0, 0x0f, 0xac, 4]);
+    let home = FrameBuilder::beacon(
+        BSSID,
+        100,
+        0x0011,
+        &[&element(0, b"home")[..], &rsn].concat(),
+    );
+    assert!(rogue_alerts(&mut scanner, &home, -50).is_empty());
+
+    let open_copy = FrameBuilder::beacon([0x02; 6], 100, 0x0001, &element(0, b"home"));
+    assert_eq!(
+        rogue_alerts(&mut scanner, &open_copy, -50),
+        [Deviation::OpenCopy]
+    );
+}
+
+fn probe_response(bssid: [u8; 6], client: [u8; 6], ssid: &str) -> Dot11Header {
+    let mut builder = FrameBuilder::beacon(bssid, 100, 0x0001, &element(0, ssid.as_bytes()));
+    builder.frame_control = frame_control(0, 5, 0);
+    builder.addr1 = client;
+    Dot11Header::from_bytes(&builder.dot11()).unwrap()
+}
+
+#[test]
+fn karma_access_point() {
+    let mut karma = KarmaDetector::new(KarmaConfig::default());
+    let start = Duration::from_secs(1_600_000_000);
+    let mut inspect = |header: &Dot11Header, secs: u64| {
+        karma.inspect(&no_radiotap(), header, start + Duration::from_secs(secs))
+    };
+
+    // A genuine access point answers the probes for its own SSID only
+    let home =
+        Dot11Header::from_bytes(&FrameBuilder::probe_request(CLIENT, &element(0, b"home")).dot11())
+            .unwrap();
+    for i in 0..5 {
+        assert!(inspect(&home, i).is_empty());
+        assert!(inspect(&probe_response(BSSID, CLIENT, "home"), i).is_empty());
+    }
+    // SSIDs nobody probed for don't count
+    for ssid in &["a", "b", "c"] {
+        assert!(inspect(&probe_response(BSSID, CLIENT, ssid), 5).is_empty());
+    }
+
+    // The rogue one answers every probed SSID
+    let rogue = [0x02, 0xaa, 0xbb, 0xcc, 0xdd, 0xee];
+    let mut alerts = Vec::new();
+    for ssid in &["home", "cafe", "airport", "hotel"] {
+        let probe = FrameBuilder::probe_request(CLIENT, &element(0, ssid.as_bytes()));
+        assert!(inspect(&Dot11Header::from_bytes(&probe.dot11()).unwrap(), 10).is_empty());
+        alerts.extend(inspect(&probe_response(rogue, CLIENT, ssid), 10));
+    }
+    // Once per window
+    assert_eq!(
+        alerts,
+        [AlertKind::Karma(Karma {
+            bssid: MacAddr(rogue),
+            ssids: vec![
+                "airport".to_string(),
+                "cafe".to_string(),
+                "home".to_string()
+            ],
+        })]
+    );
+    let alert = Alert {
+        timestamp: 0,
+        kind: alerts.remove(0),
+    };
+    assert!(alert
+        .to_string()
+        .contains("02:aa:bb:cc:dd:ee answered probes for 3 SSIDs: airport, cafe, home"));
+}
+
+#[test]
+fn beacon_flood_is_quarantined() {
+    let mut scanner = Scanner::new(Mapper::new().unwrap(), false, None);
+    let start = Duration::from_secs(1_600_000_000);
+    let mut beacon = |bssid: [u8; 6], elements: &[u8], millis: u64| {
+        let beacon = FrameBuilder::beacon(bssid, 100, 0x0001, elements);
+        scanner
+            .process_at(
+                &RadiotapHeader::default().encapsulate(&beacon.dot11()),
+                start + Duration::from_millis(millis),
+            )
+            .unwrap();
+        scanner.take_alerts()
+    };
+
+    let rsn = element(48, &[1, 0, 0, 0x0f, 0xac, 4]);
+    let home = [&element(0, b"home")[..], &rsn].concat();
+    assert!(beacon(BSSID, &home, 0).is_empty());
+
+    // Random BSSIDs and SSIDs, the rest of the beacon is the same
+    let mut alerts = Vec::new();
+    for i in 0..30u8 {
+        let ssid = format!("free wifi {}", i);
+        let elements = [&element(0, ssid.as_bytes())[..], &element(3, &[i % 13 + 1])].concat();
+        alerts.extend(beacon(
+            [0x02, 0, 0, 0, 0, i],
+            &elements,
+            100 + u64::from(i) * 10,
+        ));
+    }
+    assert_eq!(
+        alerts
+            .into_iter()
+            .map(|alert| alert.kind)
+            .collect::<Vec<_>>(),
+        [AlertKind::BeaconFlood(BeaconFlood {
+            new_bssids: 21,
+            random_macs: 20,
+            largest_template: 20,
+            quarantined: 20,
+            window: 10,
+        })]
+    );
+
+    // The genuine access point keeps beaconing and stays on the map
+    assert!(beacon(BSSID, &home, 1_000).is_empty());
+    let bssids: Vec<MacAddr> = scanner.mapper.net_map.keys().copied().collect();
+    assert_eq!(bssids, [MacAddr(BSSID)]);
+    assert!(scanner.wids.is_quarantined(MacAddr([0x02, 0, 0, 0, 0, 29])));
+    assert!(!scanner.wids.is_quarantined(MacAddr(BSSID)));
+}